@@ -0,0 +1,179 @@
+/// The channel count and sample rate of interleaved audio samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioFormat {
+    pub channels: u16,
+    pub sample_rate: u32,
+}
+
+/// Converts interleaved float samples from one channel count and sample
+/// rate to another, so any recording can be played on any output device.
+///
+/// Resampling uses linear interpolation between neighbouring frames.
+/// Channels are up-mixed by duplicating mono to every channel, or copying
+/// existing channels and leaving extra ones silent, and down-mixed by
+/// averaging into mono, or dropping the channels that don't fit.
+pub struct SampleConverter<I: Iterator<Item = f32>> {
+    source: I,
+    from: AudioFormat,
+    to: AudioFormat,
+    frames_converted: u64,
+    current_frame_num: u64,
+    current_frame: Option<Vec<f32>>,
+    next_frame: Option<Vec<f32>>,
+    output_frame: Vec<f32>,
+    output_pos: usize,
+}
+
+impl<I: Iterator<Item = f32>> SampleConverter<I> {
+    pub fn new(mut source: I, from: AudioFormat, to: AudioFormat) -> SampleConverter<I> {
+        let current_frame = read_frame(&mut source, from.channels);
+        let next_frame = read_frame(&mut source, from.channels);
+
+        SampleConverter {
+            source,
+            from,
+            to,
+            frames_converted: 0,
+            current_frame_num: 0,
+            current_frame,
+            next_frame,
+            output_frame: Vec::with_capacity(to.channels as usize),
+            output_pos: 0,
+        }
+    }
+
+    /// Produces the next converted frame, or None once the source runs out.
+    fn convert_next_frame(&mut self) -> Option<()> {
+        // Kept as a ratio of integers so rounding errors don't build up over long recordings
+        let source_pos = self.frames_converted * self.from.sample_rate as u64;
+        let source_frame_num = source_pos / self.to.sample_rate as u64;
+        let fraction =
+            (source_pos % self.to.sample_rate as u64) as f32 / self.to.sample_rate as f32;
+
+        while self.current_frame_num < source_frame_num {
+            self.current_frame = self.next_frame.take();
+            self.next_frame = read_frame(&mut self.source, self.from.channels);
+            self.current_frame_num += 1;
+        }
+
+        let current_frame = self.current_frame.as_ref()?;
+        let interpolated: Vec<f32> = match &self.next_frame {
+            Some(next_frame) => current_frame
+                .iter()
+                .zip(next_frame)
+                .map(|(current, next)| current + (next - current) * fraction)
+                .collect(),
+            None => current_frame.clone(),
+        };
+
+        self.output_frame.clear();
+        map_channels(&interpolated, self.to.channels, &mut self.output_frame);
+        self.output_pos = 0;
+        self.frames_converted += 1;
+
+        Some(())
+    }
+}
+
+impl<I: Iterator<Item = f32>> Iterator for SampleConverter<I> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.output_pos >= self.output_frame.len() {
+            self.convert_next_frame()?;
+        }
+
+        let sample = self.output_frame[self.output_pos];
+        self.output_pos += 1;
+        Some(sample)
+    }
+}
+
+/// Reads a whole frame from the source, discarding any incomplete one at the end.
+fn read_frame<I: Iterator<Item = f32>>(source: &mut I, channels: u16) -> Option<Vec<f32>> {
+    let frame: Vec<f32> = source.by_ref().take(channels as usize).collect();
+
+    if frame.len() == channels as usize && !frame.is_empty() {
+        Some(frame)
+    } else {
+        None
+    }
+}
+
+fn map_channels(frame: &[f32], channels: u16, output: &mut Vec<f32>) {
+    let channels = channels as usize;
+
+    if frame.len() == channels {
+        output.extend_from_slice(frame);
+    } else if frame.len() == 1 {
+        output.extend(std::iter::repeat_n(frame[0], channels));
+    } else if channels == 1 {
+        output.push(frame.iter().sum::<f32>() / frame.len() as f32);
+    } else {
+        output.extend((0..channels).map(|channel| frame.get(channel).copied().unwrap_or(0.0)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(samples: &[f32], from: (u16, u32), to: (u16, u32)) -> Vec<f32> {
+        SampleConverter::new(
+            samples.iter().copied(),
+            AudioFormat {
+                channels: from.0,
+                sample_rate: from.1,
+            },
+            AudioFormat {
+                channels: to.0,
+                sample_rate: to.1,
+            },
+        )
+        .collect()
+    }
+
+    #[test]
+    fn same_format_unchanged() {
+        let samples = [0.1, -0.2, 0.3, -0.4];
+        assert_eq!(convert(&samples, (2, 44100), (2, 44100)), samples);
+    }
+
+    #[test]
+    fn maps_channels() {
+        assert_eq!(
+            convert(&[0.5, -0.5], (1, 48000), (2, 48000)),
+            [0.5, 0.5, -0.5, -0.5]
+        );
+        assert_eq!(
+            convert(&[0.5, 0.25, -0.5, 0.0], (2, 48000), (1, 48000)),
+            [0.375, -0.25]
+        );
+        assert_eq!(
+            convert(&[0.5, 0.25], (2, 48000), (4, 48000)),
+            [0.5, 0.25, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn upsamples_by_interpolating() {
+        assert_eq!(
+            convert(&[0.0, 1.0, 0.0], (1, 24000), (1, 48000)),
+            [0.0, 0.5, 1.0, 0.5, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn resamples_to_expected_length() {
+        let samples: Vec<f32> = (0..44100).map(|n| (n % 100) as f32 / 100.0).collect();
+        let converted = convert(&samples, (1, 44100), (2, 48000));
+
+        assert_eq!(converted.len(), 48000 * 2);
+        assert_eq!(convert(&converted, (2, 48000), (1, 8000)).len(), 8000);
+    }
+
+    #[test]
+    fn drops_incomplete_frames() {
+        assert_eq!(convert(&[0.5, 0.5, 0.25], (2, 8000), (2, 8000)), [0.5, 0.5]);
+    }
+}
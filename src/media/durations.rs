@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::media::io::audio_duration_ms;
+
+/// Keeps how long every recording measured is, so listing them all again
+/// doesn't open each of their files. Files changed since they were
+/// measured, like a take that was still being recorded, are measured afresh.
+#[derive(Default)]
+pub struct DurationCache {
+    /// Durations by file, along with when it was changed last. Files that
+    /// couldn't be read have no duration.
+    entries: HashMap<PathBuf, (SystemTime, Option<usize>)>,
+}
+
+impl DurationCache {
+    /// Returns how long the recording was in milliseconds when it was
+    /// measured, if it hasn't changed since.
+    pub fn get(&self, audio_file: &Path) -> Option<usize> {
+        let modified = last_modified(audio_file)?;
+
+        self.entries
+            .get(audio_file)
+            .filter(|(cached_modified, _)| *cached_modified == modified)
+            .and_then(|(_, duration_ms)| *duration_ms)
+    }
+
+    /// Returns whether the recording is there but hasn't been measured
+    /// since it was last changed.
+    pub fn needs_measuring(&self, audio_file: &Path) -> bool {
+        let Some(modified) = last_modified(audio_file) else {
+            return false;
+        };
+
+        self.entries
+            .get(audio_file)
+            .is_none_or(|(cached_modified, _)| *cached_modified != modified)
+    }
+
+    /// Returns how long the recording is in milliseconds, only reading the
+    /// file if it hasn't been measured since it was last changed.
+    pub fn measure(&mut self, audio_file: &Path) -> Option<usize> {
+        let modified = last_modified(audio_file)?;
+
+        if let Some((cached_modified, duration_ms)) = self.entries.get(audio_file) {
+            if *cached_modified == modified {
+                return *duration_ms;
+            }
+        }
+
+        let duration_ms = audio_duration_ms(audio_file).ok();
+        self.entries
+            .insert(audio_file.to_path_buf(), (modified, duration_ms));
+
+        duration_ms
+    }
+}
+
+fn last_modified(audio_file: &Path) -> Option<SystemTime> {
+    audio_file
+        .metadata()
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recordings_are_measured_once() {
+        let directory = tempfile::tempdir().unwrap();
+        let recording = directory.path().join("part0_take1.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+
+        let mut writer = hound::WavWriter::create(&recording, spec).unwrap();
+        for sample in 0..4000 {
+            writer.write_sample(sample as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut cache = DurationCache::default();
+        assert!(cache.needs_measuring(&recording));
+        assert_eq!(cache.get(&recording), None);
+
+        assert_eq!(cache.measure(&recording), Some(500));
+        assert!(!cache.needs_measuring(&recording));
+        assert_eq!(cache.get(&recording), Some(500));
+    }
+
+    #[test]
+    fn unreadable_recordings_are_measured_once() {
+        let directory = tempfile::tempdir().unwrap();
+        let unreadable = directory.path().join("part0_take1.wav");
+        std::fs::write(&unreadable, b"Not a WAV file.").unwrap();
+        let missing = directory.path().join("part1_take1.wav");
+
+        let mut cache = DurationCache::default();
+        assert_eq!(cache.measure(&unreadable), None);
+        assert!(!cache.needs_measuring(&unreadable));
+
+        assert_eq!(cache.measure(&missing), None);
+        assert!(!cache.needs_measuring(&missing));
+    }
+}
@@ -0,0 +1,270 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use hound::{WavReader, WavSpec, WavWriter};
+
+use crate::media::convert::{AudioFormat, SampleConverter};
+use crate::media::io::decoded_samples;
+
+/// What ended up in an exported audiobook.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExportReport {
+    pub num_exported: usize,
+    /// Positions of paragraphs left out for having no recording.
+    pub unrecorded: Vec<usize>,
+}
+
+impl ExportReport {
+    /// Lists the paragraphs without a recording by their number and
+    /// first words, so they can be found and recorded.
+    pub fn unrecorded_summary(&self, paragraphs: &[String]) -> String {
+        self.unrecorded
+            .iter()
+            .map(|&paragraph_num| {
+                let first_words = paragraphs
+                    .get(paragraph_num)
+                    .map(|paragraph| {
+                        paragraph
+                            .split_whitespace()
+                            .take(8)
+                            .collect::<Vec<&str>>()
+                            .join(" ")
+                    })
+                    .unwrap_or_default();
+
+                format!("Paragraph {}: {first_words}\n", paragraph_num + 1)
+            })
+            .collect()
+    }
+}
+
+/// Concatenates the recording of each paragraph in order into one file,
+/// with a stretch of silence between paragraphs. Every recording is
+/// converted to the sample rate, channels and sample format of the
+/// first one, so takes made with different devices still fit together.
+///
+/// The file is only put in place once every recording made it in, so a
+/// recording that can't be read leaves nothing half written behind.
+pub fn export_recordings(
+    recordings: &[Option<PathBuf>],
+    gap_ms: usize,
+    output_file: &Path,
+) -> Result<ExportReport> {
+    let Some(first_recording) = recordings.iter().find_map(existing_recording) else {
+        bail!("There are no recordings to export.");
+    };
+
+    let mut partial_file = output_file.as_os_str().to_owned();
+    partial_file.push(".part");
+    let partial_file = PathBuf::from(partial_file);
+
+    let exported = write_recordings(recordings, first_recording, gap_ms, &partial_file);
+    let report = exported.and_then(|report| {
+        std::fs::rename(&partial_file, output_file)?;
+        Ok(report)
+    });
+    if report.is_err() {
+        std::fs::remove_file(&partial_file).ok();
+    }
+
+    report
+}
+
+/// Writes every recording into the output file, in the format of the
+/// first recording.
+fn write_recordings(
+    recordings: &[Option<PathBuf>],
+    first_recording: &Path,
+    gap_ms: usize,
+    output_file: &Path,
+) -> Result<ExportReport> {
+    let spec = WavReader::open(first_recording)?.spec();
+    let export_format = AudioFormat {
+        channels: spec.channels,
+        sample_rate: spec.sample_rate,
+    };
+    let gap_samples =
+        (gap_ms as u64 * spec.sample_rate as u64 / 1000) as usize * spec.channels as usize;
+
+    let mut writer = WavWriter::create(output_file, spec)?;
+    let mut report = ExportReport {
+        num_exported: 0,
+        unrecorded: Vec::new(),
+    };
+
+    for (paragraph_num, recording) in recordings.iter().enumerate() {
+        let Some(recording) = existing_recording(recording) else {
+            report.unrecorded.push(paragraph_num);
+            continue;
+        };
+
+        if report.num_exported > 0 {
+            for _ in 0..gap_samples {
+                write_sample(&mut writer, spec, 0.0)?;
+            }
+        }
+
+        let file_decoder = WavReader::open(recording)?;
+        let file_spec = file_decoder.spec();
+        let samples = SampleConverter::new(
+            decoded_samples(file_decoder)?,
+            AudioFormat {
+                channels: file_spec.channels,
+                sample_rate: file_spec.sample_rate,
+            },
+            export_format,
+        );
+
+        for sample in samples {
+            write_sample(&mut writer, spec, sample)?;
+        }
+
+        report.num_exported += 1;
+    }
+
+    writer.finalize()?;
+
+    Ok(report)
+}
+
+/// Returns the location of the recording, unless it was never made or has since been removed.
+fn existing_recording(recording: &Option<PathBuf>) -> Option<&PathBuf> {
+    recording.as_ref().filter(|location| location.is_file())
+}
+
+/// Writes a float sample in the sample format of the exported file.
+fn write_sample(writer: &mut WavWriter<BufWriter<File>>, spec: WavSpec, sample: f32) -> Result<()> {
+    let sample = sample.clamp(-1.0, 1.0);
+
+    match (spec.sample_format, spec.bits_per_sample) {
+        (hound::SampleFormat::Float, 32) => writer.write_sample(sample)?,
+        (hound::SampleFormat::Int, bits_per_sample @ 8..=32) => {
+            let full_scale = ((1i64 << (bits_per_sample - 1)) - 1) as f32;
+            writer.write_sample((sample * full_scale) as i32)?
+        }
+        (sample_format, bits_per_sample) => {
+            bail!(
+                "Unsupported SampleFormat {sample_format:?} with {bits_per_sample} bits found for exporting."
+            )
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_recording(directory: &Path, name: &str, spec: WavSpec, num_frames: usize) -> PathBuf {
+        let location = directory.join(name);
+
+        let mut writer = WavWriter::create(&location, spec).unwrap();
+        for _ in 0..num_frames * spec.channels as usize {
+            writer.write_sample(1000i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        location
+    }
+
+    #[test]
+    fn concatenates_recordings_with_gaps() {
+        let directory = tempfile::tempdir().unwrap();
+        let mono_spec = WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let stereo_spec = WavSpec {
+            channels: 2,
+            sample_rate: 16000,
+            ..mono_spec
+        };
+
+        let recordings = [
+            None,
+            Some(create_recording(
+                directory.path(),
+                "part1.wav",
+                mono_spec,
+                800,
+            )),
+            Some(directory.path().join("missing.wav")),
+            Some(create_recording(
+                directory.path(),
+                "part3.wav",
+                stereo_spec,
+                1600,
+            )),
+        ];
+
+        let output_file = directory.path().join("export.wav");
+        let report = export_recordings(&recordings, 500, &output_file).unwrap();
+
+        assert_eq!(report.num_exported, 2);
+        assert_eq!(report.unrecorded, [0, 2]);
+
+        let exported = WavReader::open(&output_file).unwrap();
+        assert_eq!(exported.spec(), mono_spec);
+        // 100ms, then a 500ms gap, then the second 100ms converted to mono 8kHz
+        assert_eq!(exported.duration(), 800 + 4000 + 800);
+    }
+
+    #[test]
+    fn nothing_recorded_fails() {
+        let directory = tempfile::tempdir().unwrap();
+        let output_file = directory.path().join("export.wav");
+
+        assert!(export_recordings(&[None, None], 1000, &output_file).is_err());
+        assert!(!output_file.exists());
+    }
+
+    #[test]
+    fn unreadable_recording_leaves_nothing_behind() {
+        let directory = tempfile::tempdir().unwrap();
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let broken_recording = directory.path().join("part1.wav");
+        std::fs::write(&broken_recording, "Not a recording.").unwrap();
+
+        let recordings = [
+            Some(create_recording(directory.path(), "part0.wav", spec, 800)),
+            Some(broken_recording),
+        ];
+
+        let output_file = directory.path().join("export.wav");
+        std::fs::write(&output_file, "Exported before.").unwrap();
+
+        assert!(export_recordings(&recordings, 500, &output_file).is_err());
+        assert_eq!(
+            std::fs::read_to_string(&output_file).unwrap(),
+            "Exported before."
+        );
+        assert!(!directory.path().join("export.wav.part").exists());
+    }
+
+    #[test]
+    fn summarizes_unrecorded_paragraphs() {
+        let report = ExportReport {
+            num_exported: 1,
+            unrecorded: vec![1],
+        };
+        let paragraphs = [
+            String::from("Recorded."),
+            String::from("It was a dark and stormy night; the rain fell in torrents."),
+        ];
+
+        assert_eq!(
+            report.unrecorded_summary(&paragraphs),
+            "Paragraph 2: It was a dark and stormy night; the\n"
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use std::fs::File;
-use std::io::BufWriter;
-use std::path::PathBuf;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::{Arc, Mutex, RwLock};
 
@@ -10,11 +11,11 @@ use fltk::prelude::{DisplayExt, ValuatorExt, WidgetExt};
 use fltk::text::TextDisplay;
 use fltk::valuator::HorNiceSlider;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{
-    default_host, Device, FromSample, Sample, SampleRate, Stream, StreamConfig,
+    default_host, Device, FromSample, Sample, SampleRate, SizedSample, Stream, StreamConfig,
     SupportedStreamConfig,
 };
 use hound::{WavReader, WavSpec, WavWriter};
@@ -23,47 +24,61 @@ use serde::{Deserialize, Serialize};
 
 use anyhow::{bail, Result};
 
-use crate::ui::app::{MainUIWidgets, MediaTrackingWidgets};
+use crate::media::convert::{AudioFormat, SampleConverter};
+use crate::media::levels::{InputLevels, InputLevelsHandle};
+use crate::media::peaks::{PeakCache, Peaks};
+use crate::ui::app::{MainUIWidgets, MediaTrackingWidgets, UIActions};
+use crate::ui::waveform::Waveform;
 
 #[derive(Clone)]
 struct PlaybackWidget {
+    waveform: Waveform,
     time_label: Frame,
     progress_bar: HorNiceSlider,
     status_bar: TextDisplay,
 }
 
-/// Converts seconds to hours:minutes:seconds format
-fn to_hh_mm_ss_str(secs: usize) -> String {
+/// Converts milliseconds to hours:minutes:seconds format
+fn to_hh_mm_ss_str(pos_ms: usize) -> String {
+    let secs = pos_ms / 1000;
     let seconds = secs % 60;
     let minutes = (secs / 60) % 60;
-    let hours = minutes / 60;
+    let hours = secs / 3600;
 
     format!("{hours:02}:{minutes:02}:{seconds:02}")
 }
 
 impl PlaybackWidget {
     pub fn new(
+        waveform: Waveform,
         time_label: Frame,
         progress_bar: HorNiceSlider,
         status_bar: TextDisplay,
     ) -> PlaybackWidget {
         PlaybackWidget {
+            waveform,
             time_label,
             progress_bar,
             status_bar,
         }
     }
 
-    pub fn set_current(&mut self, pos_secs: usize) {
-        self.progress_bar.set_value(pos_secs as f64);
+    pub fn set_current(&mut self, pos_ms: usize) {
+        self.progress_bar.set_value(pos_ms as f64);
+        self.waveform.set_position(pos_ms);
     }
 
     pub fn current(&self) -> usize {
         self.progress_bar.value() as usize
     }
 
-    pub fn set_total(&mut self, total_secs: usize) {
-        self.progress_bar.set_bounds(0.0, total_secs as f64);
+    pub fn set_total(&mut self, total_ms: usize) {
+        self.progress_bar.set_bounds(0.0, total_ms as f64);
+        self.waveform.set_total(total_ms);
+    }
+
+    pub fn set_peaks(&mut self, peaks: Option<Arc<Peaks>>) {
+        self.waveform.set_peaks(peaks);
     }
 
     pub fn total(&self) -> usize {
@@ -95,7 +110,8 @@ impl PlaybackWidget {
     }
 
     pub fn reset(&mut self) {
-        self.progress_bar.set_bounds(0.0, 0.0);
+        self.set_total(0);
+        self.set_peaks(None);
         self.clear_notification();
     }
 
@@ -107,6 +123,22 @@ impl PlaybackWidget {
         app::awake();
     }
 
+    pub fn notify_punching_in(&mut self, pos_ms: usize) {
+        self.status_bar
+            .buffer()
+            .unwrap()
+            .set_text(&format!("Punching in at {}", to_hh_mm_ss_str(pos_ms)));
+        app::awake();
+    }
+
+    pub fn notify_recording_paused(&mut self) {
+        self.status_bar
+            .buffer()
+            .unwrap()
+            .set_text("Recording paused. Press Record to resume.");
+        app::awake();
+    }
+
     pub fn clear_notification(&mut self) {
         self.status_bar.buffer().unwrap().set_text("");
         app::awake();
@@ -118,6 +150,7 @@ enum MediaStates {
     Playing,
     Paused,
     Recording,
+    PausedRecording,
 
     StoppedPlaying,
     StoppedRecording,
@@ -126,29 +159,47 @@ enum MediaStates {
 pub struct Media {
     stream_updater: Sender<SenderMessages>,
     media_state: Arc<RwLock<MediaStates>>,
+    /// Why the last recording couldn't be started, until it's reported.
+    recording_error: Arc<Mutex<Option<String>>>,
 
     audio_location: Option<PathBuf>,
 }
 
+/// Describes an existing recording to punch into, where everything
+/// before the current position is kept and the rest is re-recorded.
+pub struct PunchIn {
+    pub source_file: PathBuf,
+    pub preroll_secs: usize,
+    pub output_device: AudioOutput,
+}
+
+/// How often the playback and recording widgets are refreshed.
+const UI_REFRESH_INTERVAL: Duration = Duration::from_millis(50);
+
 enum SenderMessages {
-    Load(usize),
-    Clear,
+    Load(usize, PathBuf),
+    /// Resets the playback widgets, along with whether there's anything
+    /// to record.
+    Clear(bool),
 
     Play(AudioOutput, PathBuf),
-    Record(AudioInput, PathBuf),
+    Record(AudioInput, PathBuf, Option<PunchIn>),
     PauseAt(usize),
     StopIfPaused,
 }
 
 fn spawn_media_ui_modifier(
     media_state: Arc<RwLock<MediaStates>>,
+    recording_error: Arc<Mutex<Option<String>>>,
     msg_receiver: Receiver<SenderMessages>,
+    action_sender: app::Sender<UIActions>,
     mut playback_widget: PlaybackWidget,
     mut ui_widgets: MainUIWidgets,
 ) {
     thread::spawn(move || {
         let mut prev_button_active = false;
         let mut next_button_active = false;
+        let mut peak_cache = PeakCache::default();
 
         while let Ok(sender_msg) = msg_receiver.recv() {
             match sender_msg {
@@ -166,30 +217,33 @@ fn spawn_media_ui_modifier(
                     ui_widgets.play_button.set_label("Pause");
                     ui_widgets.record_button.deactivate();
                     ui_widgets.stop_button.activate();
+                    ui_widgets.take_selector.deactivate();
                     ui_widgets.open_menu_item.deactivate();
                     app::awake();
 
-                    let mut current_pos_secs = playback_widget.current();
-                    let total_secs = playback_widget.total();
-                    let (_audio, _) = output_stream_from(
+                    let starting_pos_ms = playback_widget.current();
+                    let total_ms = playback_widget.total();
+                    let (_audio, playback_position) = output_stream_from(
                         output_device.to_device(),
-                        current_pos_secs,
+                        starting_pos_ms,
                         audio_file_path,
                     )
                     .expect("Could not start playing audio.");
 
+                    let mut current_pos_ms = starting_pos_ms;
                     while *media_state
                         .read()
                         .expect("Could not check for playing state")
                         == MediaStates::Playing
-                        && current_pos_secs < total_secs
+                        && current_pos_ms < total_ms
                     {
-                        thread::sleep(Duration::from_secs(1));
-                        current_pos_secs += 1;
-                        playback_widget.set_current(current_pos_secs);
+                        thread::sleep(UI_REFRESH_INTERVAL);
+                        current_pos_ms =
+                            (starting_pos_ms + playback_position.elapsed_ms()).min(total_ms);
+                        playback_widget.set_current(current_pos_ms);
                         playback_widget.update_playback();
 
-                        if current_pos_secs == total_secs {
+                        if current_pos_ms == total_ms {
                             *media_state.write().expect(
                                 "Could not change state to stoppedplaying on reaching duration",
                             ) = MediaStates::StoppedPlaying;
@@ -205,6 +259,7 @@ fn spawn_media_ui_modifier(
                         ui_widgets.play_button.set_label("Play");
                         ui_widgets.record_button.activate();
                         ui_widgets.stop_button.deactivate();
+                        ui_widgets.take_selector.activate();
                         ui_widgets.open_menu_item.activate();
 
                         if prev_button_active {
@@ -219,53 +274,141 @@ fn spawn_media_ui_modifier(
                         playback_widget.update_playback();
                     }
                 }
-                SenderMessages::Record(input_device, new_audio_file_path) => {
+                SenderMessages::Record(input_device, new_audio_file_path, punch_in) => {
                     prev_button_active = ui_widgets.prev_button.active();
                     ui_widgets.prev_button.deactivate();
                     next_button_active = ui_widgets.next_button.active();
                     ui_widgets.next_button.deactivate();
 
                     ui_widgets.open_menu_item.deactivate();
-                    ui_widgets.play_button.deactivate();
+                    ui_widgets.play_button.set_label("Pause");
+                    ui_widgets.play_button.activate();
                     ui_widgets.stop_button.activate();
                     ui_widgets.record_button.deactivate();
+                    ui_widgets.take_selector.deactivate();
                     app::awake();
 
-                    let recording_status = input_stream_from(
-                        input_device.to_device(),
-                        input_device.config(),
-                        new_audio_file_path.clone(),
-                    );
-                    if recording_status.is_err() {
-                        continue;
-                    }
+                    playback_widget.set_peaks(None);
+
+                    let mut starting_pos_ms = 0;
+                    let recording_status = match punch_in {
+                        Some(punch_in) => {
+                            starting_pos_ms = playback_widget.current();
+                            playback_widget.notify_punching_in(starting_pos_ms);
+
+                            play_preroll(&punch_in, starting_pos_ms);
+                            punch_in_stream_from(
+                                input_device.to_device(),
+                                input_device.config(),
+                                punch_in.source_file,
+                                starting_pos_ms,
+                                new_audio_file_path.clone(),
+                            )
+                        }
+                        None => input_stream_from(
+                            input_device.to_device(),
+                            input_device.config(),
+                            new_audio_file_path.clone(),
+                        ),
+                    };
+                    let (_recording_stream, audio_file_handle, input_levels) =
+                        match recording_status {
+                            Ok(recording) => recording,
+                            Err(error) => {
+                                // Nothing was recorded, so there's no take to keep
+                                std::fs::remove_file(&new_audio_file_path).ok();
+                                *recording_error
+                                    .lock()
+                                    .expect("Could not keep why recording failed.") =
+                                    Some(format!("{error:#}"));
+                                *media_state
+                                    .write()
+                                    .expect("Could not change state on failing to record.") =
+                                    MediaStates::StoppedRecording;
+
+                                if prev_button_active {
+                                    ui_widgets.prev_button.activate();
+                                }
+
+                                if next_button_active {
+                                    ui_widgets.next_button.activate();
+                                }
+
+                                ui_widgets.open_menu_item.activate();
+                                ui_widgets.play_button.set_label("Play");
+                                ui_widgets.stop_button.deactivate();
+                                ui_widgets.record_button.activate();
+                                playback_widget.set_current(0);
+                                playback_widget.update_playback();
+
+                                action_sender.send(UIActions::RecordingFailed);
+                                continue;
+                            }
+                        };
+                    action_sender.send(UIActions::RecordingStarted);
+
+                    // While paused, the writer is held here instead of in the handle,
+                    // so the input stream has nowhere to send its samples until it is
+                    // handed back on resume.
+                    let mut paused_writer = None;
+                    let mut recorded_time = Duration::from_millis(starting_pos_ms as u64);
+                    let mut last_tick = Instant::now();
+                    loop {
+                        let current_state = *media_state
+                            .read()
+                            .expect("Could not check if in recording state.");
+
+                        match current_state {
+                            MediaStates::Recording => {
+                                if let Some(writer) = paused_writer.take() {
+                                    audio_file_handle
+                                        .lock()
+                                        .expect("Could not resume writing to audio file.")
+                                        .replace(writer);
+
+                                    ui_widgets.play_button.activate();
+                                    ui_widgets.record_button.deactivate();
+                                    playback_widget.clear_notification();
+                                }
+
+                                recorded_time += last_tick.elapsed();
+                            }
+                            MediaStates::PausedRecording => {
+                                if paused_writer.is_none() {
+                                    paused_writer = audio_file_handle
+                                        .lock()
+                                        .expect("Could not pause writing to audio file.")
+                                        .take();
+
+                                    ui_widgets.play_button.deactivate();
+                                    ui_widgets.record_button.activate();
+                                    playback_widget.notify_recording_paused();
+                                }
+                            }
+                            _ => break,
+                        }
+                        last_tick = Instant::now();
 
-                    let (_recording_stream, audio_file_handle) =
-                        recording_status.expect("Could not start recording.");
+                        let recorded_ms = recorded_time.as_millis() as usize;
+                        playback_widget.set_current(recorded_ms);
+                        playback_widget.set_total(recorded_ms);
+                        playback_widget.update_recording();
 
-                    let mut current_pos_secs = 0;
-                    while *media_state
-                        .read()
-                        .expect("Could not check if in recording state.")
-                        == MediaStates::Recording
-                    {
-                        thread::sleep(Duration::from_secs(1));
-                        current_pos_secs += 1;
+                        let levels = std::mem::take(
+                            &mut *input_levels.lock().expect("Could not read input levels."),
+                        );
+                        ui_widgets.level_meter.set_levels(levels);
 
-                        playback_widget.set_current(current_pos_secs);
-                        playback_widget.set_total(current_pos_secs);
-                        playback_widget.update_recording();
+                        thread::sleep(UI_REFRESH_INTERVAL);
                     }
+                    ui_widgets.level_meter.reset();
 
-                    audio_file_handle
-                        .lock()
-                        .unwrap()
-                        .take()
+                    paused_writer
+                        .or_else(|| audio_file_handle.lock().unwrap().take())
                         .unwrap()
                         .finalize()
                         .expect("Could not wrap up finishing writing audio file.");
 
-                    // NOTE: Pausing is not currently supported, so the state should only be in StoppedRecording.
                     let current_state = *media_state
                         .read()
                         .expect("Could not check if in StoppedRecording state.");
@@ -280,23 +423,27 @@ fn spawn_media_ui_modifier(
                     }
 
                     ui_widgets.open_menu_item.activate();
+                    ui_widgets.play_button.set_label("Play");
                     ui_widgets.play_button.activate();
                     ui_widgets.stop_button.deactivate();
                     ui_widgets.record_button.activate();
+                    ui_widgets.take_selector.activate();
 
                     playback_widget
                         .notify_recording_complete(new_audio_file_path.to_str().unwrap());
+                    playback_widget.set_peaks(peak_cache.get(&new_audio_file_path).ok());
                     playback_widget.set_current(0);
                     playback_widget.update_playback();
                 }
-                SenderMessages::PauseAt(current_pos_secs) => {
-                    playback_widget.set_current(current_pos_secs);
+                SenderMessages::PauseAt(current_pos_ms) => {
+                    playback_widget.set_current(current_pos_ms);
                     playback_widget.update_playback();
                 }
                 SenderMessages::StopIfPaused => {
                     ui_widgets.play_button.set_label("Play");
                     ui_widgets.record_button.activate();
                     ui_widgets.stop_button.deactivate();
+                    ui_widgets.take_selector.activate();
                     ui_widgets.open_menu_item.activate();
 
                     if prev_button_active {
@@ -310,11 +457,12 @@ fn spawn_media_ui_modifier(
                     playback_widget.set_current(0);
                     playback_widget.update_playback();
                 }
-                SenderMessages::Load(length) => {
+                SenderMessages::Load(length, audio_file_path) => {
                     playback_widget.clear_notification();
 
                     playback_widget.set_current(0);
                     playback_widget.set_total(length);
+                    playback_widget.set_peaks(peak_cache.get(&audio_file_path).ok());
 
                     ui_widgets.play_button.activate();
                     ui_widgets.stop_button.deactivate();
@@ -323,13 +471,17 @@ fn spawn_media_ui_modifier(
 
                     playback_widget.update_playback();
                 }
-                SenderMessages::Clear => {
+                SenderMessages::Clear(recordable) => {
                     playback_widget.reset();
 
                     ui_widgets.play_button.deactivate();
                     ui_widgets.stop_button.deactivate();
 
-                    ui_widgets.record_button.activate();
+                    if recordable {
+                        ui_widgets.record_button.activate();
+                    } else {
+                        ui_widgets.record_button.deactivate();
+                    }
 
                     playback_widget.update_playback();
                 }
@@ -340,21 +492,35 @@ fn spawn_media_ui_modifier(
 }
 
 impl Media {
-    pub fn new(ui_widgets: MainUIWidgets, media_widgets: MediaTrackingWidgets) -> Media {
+    pub fn new(
+        action_sender: &app::Sender<UIActions>,
+        ui_widgets: MainUIWidgets,
+        media_widgets: MediaTrackingWidgets,
+    ) -> Media {
         let playback_widget = PlaybackWidget::new(
+            media_widgets.waveform,
             media_widgets.time_progress_label,
             media_widgets.progress_bar,
             media_widgets.status_bar,
         );
 
         let media_state = Arc::new(RwLock::new(MediaStates::StoppedPlaying));
+        let recording_error = Arc::new(Mutex::new(None));
 
         let (stream_updater, rx) = mpsc::channel();
-        spawn_media_ui_modifier(media_state.clone(), rx, playback_widget, ui_widgets);
+        spawn_media_ui_modifier(
+            media_state.clone(),
+            recording_error.clone(),
+            rx,
+            *action_sender,
+            playback_widget,
+            ui_widgets,
+        );
 
         Media {
             stream_updater,
             media_state,
+            recording_error,
 
             audio_location: None,
         }
@@ -363,31 +529,46 @@ impl Media {
     pub fn load(&mut self, audio_file_location: PathBuf) {
         self.audio_location = Some(audio_file_location.clone());
 
-        let host = cpal::default_host();
-        let default_output_device = host
-            .default_output_device()
-            .expect("Unable to get default output device.");
-
-        match output_stream_from(default_output_device, 0, audio_file_location) {
-            Ok((_, length)) => {
+        match audio_duration_ms(&audio_file_location) {
+            Ok(length) => {
                 self.stream_updater
-                    .send(SenderMessages::Load(length))
+                    .send(SenderMessages::Load(length, audio_file_location))
                     .expect("Load: Could not load current audio file.");
             }
             Err(_) => {
                 self.stream_updater
-                    .send(SenderMessages::Clear)
+                    .send(SenderMessages::Clear(true))
                     .expect("Load: Could not reset UI.");
             }
         }
     }
 
+    /// Forgets the currently loaded audio file, such as when a paragraph
+    /// has not been recorded yet. Recording stays off when there's no
+    /// paragraph to record, like for a text without any.
+    pub fn unload(&mut self, recordable: bool) {
+        self.audio_location = None;
+
+        self.stream_updater
+            .send(SenderMessages::Clear(recordable))
+            .expect("Unload: Could not reset UI.");
+    }
+
     pub fn play(&mut self, output_device: &AudioOutput) {
         let current_state = *self
             .media_state
             .read()
             .expect("Could not check state for pausing playback");
-        if current_state == MediaStates::Playing {
+        if current_state == MediaStates::Recording {
+            *self
+                .media_state
+                .write()
+                .expect("Could not acquire lock to change state to paused recording") =
+                MediaStates::PausedRecording;
+            return;
+        } else if current_state == MediaStates::PausedRecording {
+            return;
+        } else if current_state == MediaStates::Playing {
             *self
                 .media_state
                 .write()
@@ -407,12 +588,14 @@ impl Media {
             .expect("Could not communicate to thread to start playing");
     }
 
-    pub fn pause_at(&mut self, current_pos_secs: usize) {
-        if *self
+    /// Moves the playback position to the given milliseconds, pausing any
+    /// playback in progress.
+    pub fn pause_at(&mut self, current_pos_ms: usize) {
+        let current_state = *self
             .media_state
             .read()
-            .expect("Could not check if in recording state to prevent pausing")
-            == MediaStates::Recording
+            .expect("Could not check if in recording state to prevent pausing");
+        if current_state == MediaStates::Recording || current_state == MediaStates::PausedRecording
         {
             return;
         }
@@ -422,11 +605,15 @@ impl Media {
             .write()
             .expect("Could not acquire lock to change state to paused") = MediaStates::Paused;
         self.stream_updater
-            .send(SenderMessages::PauseAt(current_pos_secs))
+            .send(SenderMessages::PauseAt(current_pos_ms))
             .expect("Could not communicate to thread to pause playback");
     }
 
-    pub fn record(&mut self, input_device: &AudioInput) {
+    /// Starts recording from the input device to the given location, which
+    /// becomes the currently loaded audio file.
+    pub fn record(&mut self, input_device: &AudioInput, audio_file_location: PathBuf) {
+        self.audio_location = Some(audio_file_location.clone());
+
         *self
             .media_state
             .write()
@@ -434,11 +621,76 @@ impl Media {
         self.stream_updater
             .send(SenderMessages::Record(
                 input_device.clone(),
-                self.audio_location.as_ref().unwrap().clone(),
+                audio_file_location,
+                None,
             ))
             .expect("Could not communicate to thread to start recording");
     }
 
+    /// Starts recording to the given location, starting with the audio of
+    /// the punched in recording up to the current position.
+    pub fn punch_in(
+        &mut self,
+        input_device: &AudioInput,
+        audio_file_location: PathBuf,
+        punch_in: PunchIn,
+    ) {
+        self.audio_location = Some(audio_file_location.clone());
+
+        *self
+            .media_state
+            .write()
+            .expect("Could not acquire lock to change state to recording") = MediaStates::Recording;
+        self.stream_updater
+            .send(SenderMessages::Record(
+                input_device.clone(),
+                audio_file_location,
+                Some(punch_in),
+            ))
+            .expect("Could not communicate to thread to start punching in");
+    }
+
+    /// Returns why the last recording couldn't be started, if it
+    /// couldn't, forgetting about it after.
+    pub fn take_recording_error(&mut self) -> Option<String> {
+        self.recording_error
+            .lock()
+            .expect("Could not check why recording failed.")
+            .take()
+    }
+
+    /// Returns whether the loaded recording is being played or a new one
+    /// made, even if recording is paused.
+    pub fn is_busy(&self) -> bool {
+        matches!(
+            *self
+                .media_state
+                .read()
+                .expect("Could not check if playing or recording"),
+            MediaStates::Playing | MediaStates::Recording | MediaStates::PausedRecording
+        )
+    }
+
+    pub fn is_recording_paused(&self) -> bool {
+        *self
+            .media_state
+            .read()
+            .expect("Could not check if in paused recording state")
+            == MediaStates::PausedRecording
+    }
+
+    /// Continues appending to the recording that was paused.
+    pub fn resume_recording(&mut self) {
+        if !self.is_recording_paused() {
+            return;
+        }
+
+        *self
+            .media_state
+            .write()
+            .expect("Could not acquire lock to change state to recording") = MediaStates::Recording;
+    }
+
     /// Stops the current playback or recording, reverting the playback widgets
     /// back to normal.
     pub fn stop(&mut self) {
@@ -451,7 +703,9 @@ impl Media {
                 .media_state
                 .write()
                 .expect("Could not change state to StoppedPlaying") = MediaStates::StoppedPlaying;
-        } else if current_state == MediaStates::Recording {
+        } else if current_state == MediaStates::Recording
+            || current_state == MediaStates::PausedRecording
+        {
             *self
                 .media_state
                 .write()
@@ -697,10 +951,131 @@ pub fn input_device_names() -> Vec<String> {
     input_device_names
 }
 
-/// Returns a stream and duration in seconds tuple that will immediately start
-/// playing audio from the specified output device and its starting position in
-/// seconds from the location of the input file. An error is returned if something
-/// went wrong in setting it up.
+/// Keeps count of the frames handed over to the output device, so the
+/// position shown follows the audio actually being played.
+#[derive(Clone)]
+struct PlaybackPosition {
+    frames_played: Arc<AtomicUsize>,
+    sample_rate: u32,
+}
+
+impl PlaybackPosition {
+    fn new(sample_rate: u32) -> PlaybackPosition {
+        PlaybackPosition {
+            frames_played: Arc::new(AtomicUsize::new(0)),
+            sample_rate,
+        }
+    }
+
+    fn advance(&self, num_frames: usize) {
+        self.frames_played.fetch_add(num_frames, Ordering::Relaxed);
+    }
+
+    fn elapsed_ms(&self) -> usize {
+        frames_to_ms(self.frames_played.load(Ordering::Relaxed), self.sample_rate)
+    }
+}
+
+fn frames_to_ms(num_frames: usize, sample_rate: u32) -> usize {
+    (num_frames as u64 * 1000 / sample_rate as u64) as usize
+}
+
+fn ms_to_frames(pos_ms: usize, sample_rate: u32) -> usize {
+    (pos_ms as u64 * sample_rate as u64 / 1000) as usize
+}
+
+/// Returns the length of the audio file in milliseconds.
+pub fn audio_duration_ms(audio_file: &Path) -> Result<usize> {
+    let file_decoder = WavReader::open(audio_file)?;
+
+    Ok(frames_to_ms(
+        file_decoder.duration() as usize,
+        file_decoder.spec().sample_rate,
+    ))
+}
+
+/// Fills the output buffer with samples from the iterator, followed by
+/// silence once it runs out, returning how many samples came from it.
+fn fill_output_buffer<T, I>(data: &mut [T], mut samples: I) -> usize
+where
+    T: Sample,
+    I: Iterator<Item = T>,
+{
+    let mut samples_written = 0;
+    for dst in data.iter_mut() {
+        match samples.next() {
+            Some(sample) => {
+                *dst = sample;
+                samples_written += 1;
+            }
+            None => *dst = T::EQUILIBRIUM,
+        }
+    }
+
+    samples_written
+}
+
+/// Audio samples from a file, converted to floats between -1.0 and 1.0.
+pub type SampleSource = Box<dyn Iterator<Item = f32> + Send>;
+
+/// Returns the samples of the audio file as floats, whatever format they
+/// were stored in, so every recording hound can read can be played back.
+pub fn decoded_samples(file_decoder: WavReader<BufReader<File>>) -> Result<SampleSource> {
+    let file_spec = file_decoder.spec();
+
+    match (file_spec.sample_format, file_spec.bits_per_sample) {
+        (hound::SampleFormat::Float, 32) => Ok(Box::new(
+            file_decoder
+                .into_samples::<f32>()
+                .map_while(|sample| sample.ok()),
+        )),
+        (hound::SampleFormat::Int, bits_per_sample @ 8..=32) => {
+            let full_scale = (1i64 << (bits_per_sample - 1)) as f32;
+
+            Ok(Box::new(
+                file_decoder
+                    .into_samples::<i32>()
+                    .map_while(|sample| sample.ok())
+                    .map(move |sample| sample as f32 / full_scale),
+            ))
+        }
+        (sample_format, bits_per_sample) => {
+            bail!("Unsupported SampleFormat {sample_format:?} with {bits_per_sample} bits found for playback.")
+        }
+    }
+}
+
+/// Creates an output stream writing samples in the format T expected by
+/// the output device, converting from the float samples of the source.
+fn build_output_stream<T>(
+    output_device: &Device,
+    stream_config: &StreamConfig,
+    mut samples: SampleSource,
+    playback_position: PlaybackPosition,
+) -> Result<Stream>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    let channels = stream_config.channels as usize;
+    let output_data_fn = move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+        let samples_played = fill_output_buffer(data, samples.by_ref().map(T::from_sample));
+        playback_position.advance(samples_played / channels);
+    };
+
+    let output_stream = output_device.build_output_stream(
+        stream_config,
+        output_data_fn,
+        |error| eprintln!("an error occurred on stream: {error:?}"),
+        None,
+    )?;
+
+    Ok(output_stream)
+}
+
+/// Returns a stream and its playback position that will immediately start
+/// playing audio from the specified output device and its starting position
+/// in milliseconds from the location of the input file. An error is returned
+/// if something went wrong in setting it up.
 ///
 /// # Examples
 ///
@@ -710,77 +1085,86 @@ pub fn input_device_names() -> Vec<String> {
 ///         .default_output_device()
 ///         .expect("Unable to get default output device.");
 ///
-/// let default_output_config = default_output_device
-///         .default_output_config()
-///         .expect("Unable to get output's default config.");
-///
 /// let audio_path = Path::new("test.wav").to_path_buf();
 ///
-/// let output_stream_result = output_stream_from(default_output_device, default_output_config, audio_path);
+/// let output_stream_result = output_stream_from(default_output_device, 0, audio_path);
 /// assert!(output_stream_result.is_ok());
 /// ```
 fn output_stream_from(
     output_device: Device,
-    starting_pos_secs: usize,
+    starting_pos_ms: usize,
     input_file: PathBuf,
-) -> Result<(Stream, usize)> {
+) -> Result<(Stream, PlaybackPosition)> {
     let mut file_decoder = WavReader::open(input_file)?;
-    let num_samples = file_decoder.duration();
+    let num_frames = file_decoder.duration() as usize;
 
     let file_spec = file_decoder.spec();
-    let sample_rate = file_spec.sample_rate;
-    let channels = file_spec.channels;
-    let samples_to_skip = (starting_pos_secs as u32) * sample_rate;
+    let frames_to_skip = ms_to_frames(starting_pos_ms, file_spec.sample_rate);
 
-    if samples_to_skip > num_samples {
+    if frames_to_skip > num_frames {
         bail!("output_stream_from error: Starting position exceeds file time.");
     }
 
-    file_decoder.seek(samples_to_skip)?;
+    file_decoder.seek(frames_to_skip as u32)?;
+    let samples = decoded_samples(file_decoder)?;
 
     let output_config = output_device.default_output_config()?;
-    let mut stream_config: StreamConfig = output_config.into();
-    stream_config.sample_rate = SampleRate(sample_rate);
-    stream_config.channels = channels;
-
-    let output_stream = match (file_spec.bits_per_sample, file_spec.sample_format) {
-        (32, hound::SampleFormat::Float) => {
-            let output_data_fn = move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
-                for (dst, src) in data.iter_mut().zip(file_decoder.samples::<f32>()) {
-                    *dst = src.unwrap_or(0.0);
-                }
-            };
-
-            output_device.build_output_stream(
-                &stream_config,
-                output_data_fn,
-                |error| eprintln!("an error occurred on stream: {error:?}"),
-                None,
-            )?
+    let output_sample_format = output_config.sample_format();
+    let stream_config: StreamConfig = output_config.into();
+
+    // The device may not support the file's format, so convert to its own
+    let samples: SampleSource = Box::new(SampleConverter::new(
+        samples,
+        AudioFormat {
+            channels: file_spec.channels,
+            sample_rate: file_spec.sample_rate,
+        },
+        AudioFormat {
+            channels: stream_config.channels,
+            sample_rate: stream_config.sample_rate.0,
+        },
+    ));
+
+    // Frames are counted at the device's rate, which takes the same time to play
+    let playback_position = PlaybackPosition::new(stream_config.sample_rate.0);
+    let callback_position = playback_position.clone();
+
+    let output_stream = match output_sample_format {
+        cpal::SampleFormat::I8 => {
+            build_output_stream::<i8>(&output_device, &stream_config, samples, callback_position)?
         }
-        (16, hound::SampleFormat::Int) => {
-            let output_data_fn = move |data: &mut [i16], _: &cpal::OutputCallbackInfo| {
-                for (dst, src) in data.iter_mut().zip(file_decoder.samples::<i16>()) {
-                    *dst = src.unwrap_or(0);
-                }
-            };
-
-            output_device.build_output_stream(
-                &stream_config,
-                output_data_fn,
-                |error| eprintln!("an error occurred on stream: {error:?}"),
-                None,
-            )?
+        cpal::SampleFormat::I16 => {
+            build_output_stream::<i16>(&output_device, &stream_config, samples, callback_position)?
+        }
+        cpal::SampleFormat::I32 => {
+            build_output_stream::<i32>(&output_device, &stream_config, samples, callback_position)?
+        }
+        cpal::SampleFormat::I64 => {
+            build_output_stream::<i64>(&output_device, &stream_config, samples, callback_position)?
+        }
+        cpal::SampleFormat::U8 => {
+            build_output_stream::<u8>(&output_device, &stream_config, samples, callback_position)?
+        }
+        cpal::SampleFormat::U16 => {
+            build_output_stream::<u16>(&output_device, &stream_config, samples, callback_position)?
         }
-        _ => {
-            bail!("Unsupported SampleFormat found for playback.");
+        cpal::SampleFormat::U32 => {
+            build_output_stream::<u32>(&output_device, &stream_config, samples, callback_position)?
         }
+        cpal::SampleFormat::U64 => {
+            build_output_stream::<u64>(&output_device, &stream_config, samples, callback_position)?
+        }
+        cpal::SampleFormat::F32 => {
+            build_output_stream::<f32>(&output_device, &stream_config, samples, callback_position)?
+        }
+        cpal::SampleFormat::F64 => {
+            build_output_stream::<f64>(&output_device, &stream_config, samples, callback_position)?
+        }
+        sample_format => bail!("Unsupported sample format '{sample_format}' for output device."),
     };
 
-    let duration_secs = (num_samples as f64 / sample_rate as f64).round() as usize;
-
     output_stream.play()?;
-    Ok((output_stream, duration_secs))
+    Ok((output_stream, playback_position))
 }
 
 // For the remainder of these functions, the following example was used
@@ -820,6 +1204,17 @@ where
     }
 }
 
+/// Adds the levels of a block of input to those the UI has yet to display.
+fn publish_levels<T>(input: &[T], levels: &InputLevelsHandle)
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    if let Ok(mut guard) = levels.try_lock() {
+        guard.merge(InputLevels::measure(input));
+    }
+}
+
 /// Returns a stream that will immediately start recording audio from the specified
 /// input device and its configuration (Sample Rate, Channels) to the location of the
 /// input file. An error is returned if something went wrong in setting it up.
@@ -845,12 +1240,117 @@ fn input_stream_from(
     input_device: Device,
     input_config: SupportedStreamConfig,
     input_file: PathBuf,
-) -> Result<(Stream, WavWriterHandle)> {
+) -> Result<(Stream, WavWriterHandle, InputLevelsHandle)> {
     let spec = wav_spec_from_config(&input_config);
     let writer = WavWriter::create(input_file, spec)?;
+
+    input_stream_into(input_device, input_config, writer)
+}
+
+/// Copies the first amount of samples from one recording to another.
+fn copy_samples<S>(
+    source: &mut WavReader<BufReader<File>>,
+    destination: &mut WavWriter<BufWriter<File>>,
+    num_samples: usize,
+) -> Result<()>
+where
+    S: hound::Sample,
+{
+    for sample in source.samples::<S>().take(num_samples) {
+        destination.write_sample(sample?)?;
+    }
+
+    Ok(())
+}
+
+/// Returns a stream that will immediately start recording audio like
+/// input_stream_from, except the output file begins with the audio of
+/// the source file up until the starting position in milliseconds.
+///
+/// The input device has to record in the same format as the source file,
+/// since both end up in the same output file.
+fn punch_in_stream_from(
+    input_device: Device,
+    input_config: SupportedStreamConfig,
+    source_file: PathBuf,
+    starting_pos_ms: usize,
+    output_file: PathBuf,
+) -> Result<(Stream, WavWriterHandle, InputLevelsHandle)> {
+    let spec = wav_spec_from_config(&input_config);
+
+    let mut source_decoder = WavReader::open(source_file)?;
+    if source_decoder.spec() != spec {
+        bail!("punch_in_stream_from error: Input device does not match the recording's format.");
+    }
+
+    let num_kept_samples = ms_to_frames(starting_pos_ms, spec.sample_rate) * spec.channels as usize;
+    let mut writer = WavWriter::create(output_file, spec)?;
+    match (spec.bits_per_sample, spec.sample_format) {
+        (8, hound::SampleFormat::Int) => {
+            copy_samples::<i8>(&mut source_decoder, &mut writer, num_kept_samples)?
+        }
+        (16, hound::SampleFormat::Int) => {
+            copy_samples::<i16>(&mut source_decoder, &mut writer, num_kept_samples)?
+        }
+        (32, hound::SampleFormat::Int) => {
+            copy_samples::<i32>(&mut source_decoder, &mut writer, num_kept_samples)?
+        }
+        (32, hound::SampleFormat::Float) => {
+            copy_samples::<f32>(&mut source_decoder, &mut writer, num_kept_samples)?
+        }
+        _ => bail!("Unsupported SampleFormat found for punching in."),
+    }
+
+    input_stream_into(input_device, input_config, writer)
+}
+
+/// Checks that the input device records in the same format as the
+/// recording to punch into, which it has to for the two to be joined.
+pub fn check_punch_in_format(input_device: &AudioInput, source_file: &Path) -> Result<()> {
+    let source_spec = WavReader::open(source_file)?.spec();
+    let input_spec = wav_spec_from_config(&input_device.config());
+    if source_spec != input_spec {
+        bail!(
+            "The input device records in a different format than this take, so the two can't \
+            be joined. Record a new take instead, or change the input device back to how it \
+            was when the take was recorded."
+        );
+    }
+
+    Ok(())
+}
+
+/// Plays the seconds of the punched in recording leading up to the
+/// starting position, so the narrator can match what came before.
+fn play_preroll(punch_in: &PunchIn, starting_pos_ms: usize) {
+    let preroll_ms = (punch_in.preroll_secs * 1000).min(starting_pos_ms);
+    if preroll_ms == 0 {
+        return;
+    }
+
+    let preroll_stream = output_stream_from(
+        punch_in.output_device.to_device(),
+        starting_pos_ms - preroll_ms,
+        punch_in.source_file.clone(),
+    );
+
+    if preroll_stream.is_ok() {
+        thread::sleep(Duration::from_millis(preroll_ms as u64));
+    }
+}
+
+/// Hooks up the input device to an already created writer, returning
+/// a stream that immediately starts recording to it.
+fn input_stream_into(
+    input_device: Device,
+    input_config: SupportedStreamConfig,
+    writer: WavWriter<BufWriter<File>>,
+) -> Result<(Stream, WavWriterHandle, InputLevelsHandle)> {
     let writer = Arc::new(Mutex::new(Some(writer)));
+    let levels = InputLevelsHandle::default();
 
     let writer_2 = writer.clone();
+    let levels_2 = levels.clone();
 
     let err_fn = move |err| {
         eprintln!("IO Recording error: {err}");
@@ -860,25 +1360,37 @@ fn input_stream_from(
     let io_stream = match input_config.sample_format() {
         cpal::SampleFormat::I8 => input_device.build_input_stream(
             &input_config.into(),
-            move |data, _: &_| write_input_data::<i8, i8>(data, &writer_2),
+            move |data: &[i8], _: &_| {
+                write_input_data::<i8, i8>(data, &writer_2);
+                publish_levels(data, &levels_2);
+            },
             err_fn,
             None,
         )?,
         cpal::SampleFormat::I16 => input_device.build_input_stream(
             &input_config.into(),
-            move |data, _: &_| write_input_data::<i16, i16>(data, &writer_2),
+            move |data: &[i16], _: &_| {
+                write_input_data::<i16, i16>(data, &writer_2);
+                publish_levels(data, &levels_2);
+            },
             err_fn,
             None,
         )?,
         cpal::SampleFormat::I32 => input_device.build_input_stream(
             &input_config.into(),
-            move |data, _: &_| write_input_data::<i32, i32>(data, &writer_2),
+            move |data: &[i32], _: &_| {
+                write_input_data::<i32, i32>(data, &writer_2);
+                publish_levels(data, &levels_2);
+            },
             err_fn,
             None,
         )?,
         cpal::SampleFormat::F32 => input_device.build_input_stream(
             &input_config.into(),
-            move |data, _: &_| write_input_data::<f32, f32>(data, &writer_2),
+            move |data: &[f32], _: &_| {
+                write_input_data::<f32, f32>(data, &writer_2);
+                publish_levels(data, &levels_2);
+            },
             err_fn,
             None,
         )?,
@@ -891,5 +1403,63 @@ fn input_stream_from(
 
     io_stream.play()?;
 
-    Ok((io_stream, writer))
+    Ok((io_stream, writer, levels))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_hours_minutes_seconds() {
+        assert_eq!(to_hh_mm_ss_str(0), "00:00:00");
+        assert_eq!(to_hh_mm_ss_str(59_999), "00:00:59");
+        assert_eq!(to_hh_mm_ss_str(61_000), "00:01:01");
+        assert_eq!(to_hh_mm_ss_str(3_723_000), "01:02:03");
+    }
+
+    #[test]
+    fn converts_between_frames_and_ms() {
+        assert_eq!(frames_to_ms(44_100, 44_100), 1000);
+        assert_eq!(frames_to_ms(22_050, 44_100), 500);
+        assert_eq!(ms_to_frames(1500, 48_000), 72_000);
+        assert_eq!(ms_to_frames(frames_to_ms(96_000, 48_000), 48_000), 96_000);
+    }
+
+    #[test]
+    fn output_buffer_padded_with_silence() {
+        let mut buffer = [1.0f32; 6];
+        let samples_written = fill_output_buffer(&mut buffer, [0.5f32, -0.5].into_iter());
+
+        assert_eq!(samples_written, 2);
+        assert_eq!(buffer, [0.5, -0.5, 0.0, 0.0, 0.0, 0.0]);
+    }
+
+    fn decode_test_file(bits_per_sample: u16, samples: &[i32]) -> Vec<f32> {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample,
+            sample_format: hound::SampleFormat::Int,
+        };
+
+        let mut writer = WavWriter::create(file.path(), spec).unwrap();
+        for &sample in samples {
+            writer.write_sample(sample).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        decoded_samples(WavReader::open(file.path()).unwrap())
+            .unwrap()
+            .collect()
+    }
+
+    #[test]
+    fn decodes_integer_samples_to_floats() {
+        assert_eq!(decode_test_file(8, &[-128, 0, 64]), [-1.0, 0.0, 0.5]);
+        assert_eq!(decode_test_file(16, &[-32768, 16384]), [-1.0, 0.5]);
+        assert_eq!(decode_test_file(24, &[-8_388_608, 4_194_304]), [-1.0, 0.5]);
+        assert_eq!(decode_test_file(32, &[i32::MIN, 1 << 30]), [-1.0, 0.5]);
+    }
 }
@@ -0,0 +1,95 @@
+use std::sync::{Arc, Mutex};
+
+use cpal::{FromSample, Sample};
+
+/// Samples at or above this magnitude are treated as hitting full scale.
+const CLIP_THRESHOLD: f32 = 0.999;
+
+/// The loudest and average level of the input since it was last read,
+/// as magnitudes between 0.0 and 1.0.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct InputLevels {
+    pub peak: f32,
+    pub rms: f32,
+    pub clipped: bool,
+}
+
+/// Levels shared between the input stream's callback and the UI thread.
+pub type InputLevelsHandle = Arc<Mutex<InputLevels>>;
+
+impl InputLevels {
+    /// Measures the peak and RMS of a block of samples from the input device.
+    pub fn measure<T>(block: &[T]) -> InputLevels
+    where
+        T: Sample,
+        f32: FromSample<T>,
+    {
+        if block.is_empty() {
+            return InputLevels::default();
+        }
+
+        let mut peak = 0.0f32;
+        let mut sum_of_squares = 0.0f32;
+        for &sample in block {
+            let sample = f32::from_sample(sample);
+
+            peak = peak.max(sample.abs());
+            sum_of_squares += sample * sample;
+        }
+
+        InputLevels {
+            peak,
+            rms: (sum_of_squares / block.len() as f32).sqrt(),
+            clipped: peak >= CLIP_THRESHOLD,
+        }
+    }
+
+    /// Combines levels of several blocks, so none are missed between UI updates.
+    pub fn merge(&mut self, other: InputLevels) {
+        self.peak = self.peak.max(other.peak);
+        self.rms = self.rms.max(other.rms);
+        self.clipped |= other.clipped;
+    }
+}
+
+/// Converts a magnitude to decibels relative to full scale.
+pub fn to_dbfs(magnitude: f32) -> f32 {
+    20.0 * magnitude.max(f32::MIN_POSITIVE).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_peak_and_rms() {
+        let levels = InputLevels::measure(&[0.5f32, -0.5, 0.5, -0.5]);
+
+        assert_eq!(levels.peak, 0.5);
+        assert_eq!(levels.rms, 0.5);
+        assert!(!levels.clipped);
+        assert_eq!(InputLevels::measure::<f32>(&[]), InputLevels::default());
+    }
+
+    #[test]
+    fn full_scale_integers_clip() {
+        assert!(InputLevels::measure(&[0i16, i16::MIN]).clipped);
+        assert!(InputLevels::measure(&[i16::MAX, 0]).clipped);
+        assert!(!InputLevels::measure(&[i16::MAX / 2]).clipped);
+    }
+
+    #[test]
+    fn merging_keeps_loudest_and_clipping() {
+        let mut levels = InputLevels::measure(&[1.0f32]);
+        levels.merge(InputLevels::measure(&[0.25f32]));
+
+        assert_eq!(levels.peak, 1.0);
+        assert!(levels.clipped);
+    }
+
+    #[test]
+    fn converts_to_dbfs() {
+        assert_eq!(to_dbfs(1.0), 0.0);
+        assert!((to_dbfs(0.5) + 6.02).abs() < 0.01);
+    }
+}
@@ -1 +1,6 @@
+pub mod convert;
+pub mod durations;
+pub mod export;
 pub mod io;
+pub mod levels;
+pub mod peaks;
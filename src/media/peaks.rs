@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use anyhow::Result;
+use hound::WavReader;
+
+use crate::media::io::decoded_samples;
+
+/// How much audio each peak covers, fine enough for any reasonable widget width.
+const BUCKET_MS: u32 = 10;
+
+/// The lowest and highest sample of every short stretch of a recording,
+/// across all of its channels, used to draw its waveform.
+#[derive(Debug, PartialEq)]
+pub struct Peaks {
+    buckets: Vec<(f32, f32)>,
+}
+
+impl Peaks {
+    pub fn from_file(audio_file: &Path) -> Result<Peaks> {
+        let file_decoder = WavReader::open(audio_file)?;
+        let file_spec = file_decoder.spec();
+
+        Ok(Peaks::from_samples(
+            decoded_samples(file_decoder)?,
+            file_spec.channels,
+            file_spec.sample_rate,
+        ))
+    }
+
+    pub fn from_samples<I>(samples: I, channels: u16, sample_rate: u32) -> Peaks
+    where
+        I: Iterator<Item = f32>,
+    {
+        let samples_per_bucket =
+            ((sample_rate * BUCKET_MS / 1000).max(1) * channels.max(1) as u32) as usize;
+
+        let mut buckets = Vec::new();
+        let mut current_bucket = (0.0f32, 0.0f32);
+        let mut num_samples = 0;
+        for sample in samples {
+            current_bucket.0 = current_bucket.0.min(sample);
+            current_bucket.1 = current_bucket.1.max(sample);
+            num_samples += 1;
+
+            if num_samples == samples_per_bucket {
+                buckets.push(current_bucket);
+                current_bucket = (0.0, 0.0);
+                num_samples = 0;
+            }
+        }
+
+        if num_samples > 0 {
+            buckets.push(current_bucket);
+        }
+
+        Peaks { buckets }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// Returns the lowest and highest sample drawn in one of an amount of
+    /// equally wide columns spanning the whole recording.
+    pub fn column(&self, column: usize, num_columns: usize) -> (f32, f32) {
+        if self.buckets.is_empty() || num_columns == 0 {
+            return (0.0, 0.0);
+        }
+
+        let start = column * self.buckets.len() / num_columns;
+        let end = ((column + 1) * self.buckets.len() / num_columns).max(start + 1);
+
+        self.buckets[start..end]
+            .iter()
+            .fold((0.0f32, 0.0f32), |(min, max), &(low, high)| {
+                (min.min(low), max.max(high))
+            })
+    }
+}
+
+/// Keeps the peaks of every recording seen, so moving between paragraphs
+/// doesn't read their files again. Files changed since they were cached,
+/// like a re-recorded take, are read afresh.
+#[derive(Default)]
+pub struct PeakCache {
+    entries: HashMap<PathBuf, (SystemTime, Arc<Peaks>)>,
+}
+
+impl PeakCache {
+    pub fn get(&mut self, audio_file: &Path) -> Result<Arc<Peaks>> {
+        let modified = audio_file.metadata()?.modified()?;
+
+        if let Some((cached_modified, peaks)) = self.entries.get(audio_file) {
+            if *cached_modified == modified {
+                return Ok(peaks.clone());
+            }
+        }
+
+        let peaks = Arc::new(Peaks::from_file(audio_file)?);
+        self.entries
+            .insert(audio_file.to_path_buf(), (modified, peaks.clone()));
+
+        Ok(peaks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buckets_hold_min_and_max() {
+        // 100 Hz makes a single frame per bucket, two samples with stereo
+        let peaks = Peaks::from_samples([0.5, -0.25, 0.1, 0.2, -0.5].into_iter(), 2, 100);
+
+        assert_eq!(peaks.buckets, [(-0.25, 0.5), (0.0, 0.2), (-0.5, 0.0)]);
+    }
+
+    #[test]
+    fn columns_combine_or_repeat_buckets() {
+        let peaks = Peaks::from_samples([0.5, -0.5, 0.25, -0.25].into_iter(), 1, 100);
+
+        assert_eq!(peaks.column(0, 2), (-0.5, 0.5));
+        assert_eq!(peaks.column(1, 2), (-0.25, 0.25));
+        assert_eq!(peaks.column(7, 8), (-0.25, 0.0));
+        assert_eq!(
+            Peaks::from_samples([].into_iter(), 1, 100).column(0, 1),
+            (0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn cache_reuses_peaks_of_unchanged_files() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+
+        let mut writer = hound::WavWriter::create(file.path(), spec).unwrap();
+        for sample in 0..800 {
+            writer.write_sample(sample as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let mut cache = PeakCache::default();
+        let peaks = cache.get(file.path()).unwrap();
+
+        assert!(!peaks.is_empty());
+        assert!(Arc::ptr_eq(&peaks, &cache.get(file.path()).unwrap()));
+    }
+}
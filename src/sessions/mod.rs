@@ -1 +1,3 @@
 pub mod session;
+pub mod status;
+pub mod takes;
@@ -1,9 +1,27 @@
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs::{write, DirBuilder, File};
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::media::io::{AudioInput, AudioOutput};
+use crate::text::chapters::Chapter;
+use crate::text::gathering::Gathering;
+use crate::text::paragraph_ids::{paragraph_ids, paragraph_offsets};
+use crate::text::realignment::{match_paragraphs, Origin};
+use crate::text::sentences::{default_abbreviations, SentenceSplitter};
+use crate::text::source::SourceOptions;
+
+use super::status::ParagraphStatus;
+use super::takes::Takes;
+
+/// A chapter of the text, going by the paragraph it starts with so it
+/// can be found again however the paragraphs end up numbered.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+struct ChapterStart {
+    title: Option<String>,
+    first_paragraph_id: String,
+}
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
 pub struct Session {
@@ -18,6 +36,79 @@ pub struct Session {
     gathering_choice: String,
     gathering_amount: usize,
     gathering_delimiters: String,
+    #[serde(default)]
+    max_paragraph_length: usize,
+    #[serde(default = "default_words_per_minute")]
+    words_per_minute: usize,
+    #[serde(default = "default_target_duration_secs")]
+    target_duration_secs: usize,
+    #[serde(default)]
+    boundary_pattern: String,
+    #[serde(default)]
+    skipped_lines_pattern: String,
+
+    /// Takes by paragraph position, from before paragraphs had IDs.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    paragraph_takes: BTreeMap<usize, Takes>,
+    /// Takes by the ID of the paragraph they're a reading of.
+    #[serde(default)]
+    recordings: BTreeMap<String, Takes>,
+    #[serde(default)]
+    paragraph_ids: Vec<String>,
+    /// The paragraphs as they were last gathered, to tell what editing
+    /// the text changed.
+    #[serde(default)]
+    paragraph_texts: Vec<String>,
+    /// Statuses paragraphs were marked with, by their ID. Other paragraphs
+    /// go by whether they have any takes.
+    #[serde(default)]
+    statuses: BTreeMap<String, ParagraphStatus>,
+    /// Notes left on paragraphs, like direction for reading them, by
+    /// their ID.
+    #[serde(default)]
+    notes: BTreeMap<String, String>,
+    /// IDs of bookmarked paragraphs, by the name of their bookmark.
+    #[serde(default)]
+    bookmarks: BTreeMap<String, String>,
+    #[serde(default = "default_preroll_secs")]
+    preroll_secs: usize,
+    #[serde(default = "default_export_gap_ms")]
+    export_gap_ms: usize,
+
+    #[serde(default)]
+    chapter_pattern: String,
+    /// Chapters of the text as it was last gathered.
+    #[serde(default)]
+    chapters: Vec<ChapterStart>,
+    #[serde(default)]
+    read_code_blocks: bool,
+    #[serde(default)]
+    text_encoding: Option<String>,
+    #[serde(default = "default_sentence_language")]
+    sentence_language: String,
+    /// Abbreviation lists changed from the defaults, by language.
+    #[serde(default)]
+    abbreviations: BTreeMap<String, Vec<String>>,
+}
+
+fn default_preroll_secs() -> usize {
+    2
+}
+
+fn default_export_gap_ms() -> usize {
+    1000
+}
+
+fn default_words_per_minute() -> usize {
+    155
+}
+
+fn default_target_duration_secs() -> usize {
+    45
+}
+
+fn default_sentence_language() -> String {
+    String::from("English")
 }
 
 fn get_projects_path() -> PathBuf {
@@ -66,11 +157,20 @@ impl Session {
                 .expect("Could not create directory for recordings.");
         }
 
+        Session::with_project_directory(project_name, project_directory)
+    }
+
+    /// Starts a session for the project, keeping its recordings in a
+    /// directory that's already there.
+    fn with_project_directory(
+        project_file_name: String,
+        project_output_directory: PathBuf,
+    ) -> Session {
         Session {
             paragraph_num: 0,
 
-            project_file_name: project_name,
-            project_output_directory: project_directory,
+            project_file_name,
+            project_output_directory,
 
             audio_input: AudioInput::new(),
             audio_output: AudioOutput::new(),
@@ -78,6 +178,28 @@ impl Session {
             gathering_choice: String::from("Sentences"),
             gathering_amount: 4,
             gathering_delimiters: String::from(".?!"),
+            max_paragraph_length: 0,
+            words_per_minute: default_words_per_minute(),
+            target_duration_secs: default_target_duration_secs(),
+            boundary_pattern: String::new(),
+            skipped_lines_pattern: String::new(),
+
+            paragraph_takes: BTreeMap::new(),
+            recordings: BTreeMap::new(),
+            paragraph_ids: Vec::new(),
+            paragraph_texts: Vec::new(),
+            statuses: BTreeMap::new(),
+            notes: BTreeMap::new(),
+            bookmarks: BTreeMap::new(),
+            preroll_secs: default_preroll_secs(),
+            export_gap_ms: default_export_gap_ms(),
+
+            chapter_pattern: String::new(),
+            chapters: Vec::new(),
+            read_code_blocks: false,
+            text_encoding: None,
+            sentence_language: default_sentence_language(),
+            abbreviations: BTreeMap::new(),
         }
     }
 
@@ -138,6 +260,10 @@ impl Session {
         self.paragraph_num
     }
 
+    pub fn project_name(&self) -> &str {
+        &self.project_file_name
+    }
+
     pub fn set_project_directory(&mut self, new_directory: PathBuf) {
         self.project_output_directory = new_directory;
     }
@@ -185,4 +311,749 @@ impl Session {
     pub fn set_gathering_delimiters(&mut self, delimiters: &str) {
         self.gathering_delimiters = String::from(delimiters);
     }
+
+    /// Returns how many characters the text's own paragraphs can have
+    /// before they're split, or 0 for no limit.
+    pub fn max_paragraph_length(&self) -> usize {
+        self.max_paragraph_length
+    }
+
+    pub fn set_max_paragraph_length(&mut self, max_paragraph_length: usize) {
+        self.max_paragraph_length = max_paragraph_length;
+    }
+
+    /// Returns how many words a minute the narrator reads, for estimating
+    /// how long paragraphs take.
+    pub fn words_per_minute(&self) -> usize {
+        self.words_per_minute
+    }
+
+    pub fn set_words_per_minute(&mut self, words_per_minute: usize) {
+        self.words_per_minute = words_per_minute;
+    }
+
+    /// Returns about how many seconds each paragraph should take to read
+    /// when gathering by duration.
+    pub fn target_duration_secs(&self) -> usize {
+        self.target_duration_secs
+    }
+
+    pub fn set_target_duration_secs(&mut self, target_duration_secs: usize) {
+        self.target_duration_secs = target_duration_secs;
+    }
+
+    /// Returns the regular expression that pieces of text end with when
+    /// gathering by regex.
+    pub fn boundary_pattern(&self) -> String {
+        self.boundary_pattern.clone()
+    }
+
+    pub fn set_boundary_pattern(&mut self, boundary_pattern: &str) {
+        self.boundary_pattern = String::from(boundary_pattern);
+    }
+
+    /// Returns the regular expression matching lines left out when
+    /// gathering by regex, or an empty string if there is none.
+    pub fn skipped_lines_pattern(&self) -> String {
+        self.skipped_lines_pattern.clone()
+    }
+
+    pub fn set_skipped_lines_pattern(&mut self, skipped_lines_pattern: &str) {
+        self.skipped_lines_pattern = String::from(skipped_lines_pattern);
+    }
+
+    /// Returns how the text gets gathered into paragraphs, going by the
+    /// gathering choice.
+    pub fn gathering(&self) -> Gathering {
+        let splitter = SentenceSplitter::new(&self.abbreviations(&self.sentence_language));
+
+        match self.gathering_choice.as_str() {
+            "Source Paragraphs" => Gathering::Paragraphs {
+                splitter,
+                max_length: Some(self.max_paragraph_length).filter(|&max_length| max_length > 0),
+            },
+            "Smart Sentences" => Gathering::Sentences {
+                splitter,
+                amount: self.gathering_amount,
+            },
+            "Duration" => Gathering::Duration {
+                splitter,
+                words_per_minute: self.words_per_minute,
+                target_secs: self.target_duration_secs,
+            },
+            // Patterns are checked before being saved, but could still be
+            // broken by editing the session file
+            "Regex" => Gathering::pattern(
+                &self.boundary_pattern,
+                &self.skipped_lines_pattern,
+                self.gathering_amount,
+            )
+            .unwrap_or_else(|_| self.delimited_gathering()),
+            _ => self.delimited_gathering(),
+        }
+    }
+
+    fn delimited_gathering(&self) -> Gathering {
+        Gathering::Delimited {
+            delimiters: self.gathering_delimiters.clone(),
+            amount: self.gathering_amount,
+        }
+    }
+
+    /// Returns the language whose abbreviations sentences don't end at.
+    pub fn sentence_language(&self) -> String {
+        self.sentence_language.clone()
+    }
+
+    pub fn set_sentence_language(&mut self, sentence_language: &str) {
+        self.sentence_language = String::from(sentence_language);
+    }
+
+    /// Returns the abbreviations sentences in the language don't end at,
+    /// as changed for this project or otherwise the defaults.
+    pub fn abbreviations(&self, language: &str) -> Vec<String> {
+        self.abbreviations
+            .get(language)
+            .cloned()
+            .unwrap_or_else(|| default_abbreviations(language))
+    }
+
+    /// Changes the language's abbreviations, going back to the defaults
+    /// when they're the same as them.
+    pub fn set_abbreviations(&mut self, language: &str, abbreviations: Vec<String>) {
+        if abbreviations == default_abbreviations(language) {
+            self.abbreviations.remove(language);
+        } else {
+            self.abbreviations
+                .insert(String::from(language), abbreviations);
+        }
+    }
+
+    /// Returns how many seconds of the existing recording are played
+    /// before punching in.
+    pub fn preroll_secs(&self) -> usize {
+        self.preroll_secs
+    }
+
+    pub fn set_preroll_secs(&mut self, preroll_secs: usize) {
+        self.preroll_secs = preroll_secs;
+    }
+
+    /// Returns the regular expression matching chapter headings on top of
+    /// the common ones, or an empty string if there is none.
+    pub fn chapter_pattern(&self) -> String {
+        self.chapter_pattern.clone()
+    }
+
+    pub fn set_chapter_pattern(&mut self, chapter_pattern: &str) {
+        self.chapter_pattern = String::from(chapter_pattern);
+    }
+
+    /// Keeps track of the chapters found in the text, by the paragraph each
+    /// starts with. Chapters without paragraphs have nowhere to start.
+    pub fn set_chapters(&mut self, chapters: &[Chapter]) {
+        self.chapters = chapters
+            .iter()
+            .filter(|chapter| chapter.num_paragraphs > 0)
+            .filter_map(|chapter| {
+                Some(ChapterStart {
+                    title: chapter.title.clone(),
+                    first_paragraph_id: self.paragraph_id(chapter.first_paragraph)?.clone(),
+                })
+            })
+            .collect();
+    }
+
+    /// Returns the chapters of the text the way it's gathered now, along
+    /// with where their paragraphs are.
+    pub fn chapters(&self) -> Vec<Chapter> {
+        let chapter_starts: Vec<(&ChapterStart, usize)> = self
+            .chapters
+            .iter()
+            .filter_map(|chapter_start| {
+                let first_paragraph = self.paragraph_position(&chapter_start.first_paragraph_id)?;
+                Some((chapter_start, first_paragraph))
+            })
+            .collect();
+
+        chapter_starts
+            .iter()
+            .enumerate()
+            .map(|(chapter_num, (chapter_start, first_paragraph))| {
+                let next_start = chapter_starts
+                    .get(chapter_num + 1)
+                    .map_or(self.paragraph_ids.len(), |(_, next_start)| *next_start);
+
+                Chapter {
+                    title: chapter_start.title.clone(),
+                    first_paragraph: *first_paragraph,
+                    num_paragraphs: next_start - first_paragraph,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the choices about what gets read out of the text file.
+    pub fn source_options(&self) -> SourceOptions {
+        SourceOptions {
+            read_code_blocks: self.read_code_blocks,
+            encoding: self.text_encoding.clone(),
+        }
+    }
+
+    pub fn set_read_code_blocks(&mut self, read_code_blocks: bool) {
+        self.read_code_blocks = read_code_blocks;
+    }
+
+    /// Overrides the encoding the text file is read in, or with None,
+    /// goes back to working it out from the file.
+    pub fn set_text_encoding(&mut self, text_encoding: Option<&str>) {
+        self.text_encoding = text_encoding.map(String::from);
+    }
+
+    /// Returns how many milliseconds of silence separate paragraphs
+    /// when exporting the whole project.
+    pub fn export_gap_ms(&self) -> usize {
+        self.export_gap_ms
+    }
+
+    pub fn set_export_gap_ms(&mut self, export_gap_ms: usize) {
+        self.export_gap_ms = export_gap_ms;
+    }
+
+    /// Keeps track of which paragraph is at each position, so recordings
+    /// stay with the paragraph they're a reading of wherever it ends up.
+    pub fn set_paragraphs(&mut self, paragraphs: &[String]) {
+        let is_first_gathering = self.paragraph_ids.is_empty();
+        let paragraph_ids = paragraph_ids(paragraphs);
+        // Takes kept by position are from when the paragraphs were last
+        // gathered the way they are when the session is opened
+        for (paragraph_num, takes) in std::mem::take(&mut self.paragraph_takes) {
+            match paragraph_ids.get(paragraph_num) {
+                Some(paragraph_id) => {
+                    self.recordings.entry(paragraph_id.clone()).or_insert(takes);
+                }
+                None => {
+                    self.paragraph_takes.insert(paragraph_num, takes);
+                }
+            }
+        }
+
+        if !is_first_gathering {
+            self.move_notes_and_bookmarks(&paragraph_ids, paragraphs);
+        }
+
+        self.paragraph_ids = paragraph_ids;
+        self.paragraph_texts = paragraphs.to_vec();
+        // Like takes kept by position, and only then do they line up
+        if is_first_gathering {
+            self.adopt_legacy_recordings();
+        }
+    }
+
+    /// Moves the notes and bookmarks of paragraphs that aren't there in the
+    /// new paragraphs to the one that now has the text they started with.
+    /// Unlike recordings, they're still about the same part of the text.
+    /// Notes ending up on the same paragraph are put together.
+    fn move_notes_and_bookmarks(&mut self, new_ids: &[String], new_paragraphs: &[String]) {
+        if self.paragraph_texts.len() != self.paragraph_ids.len() {
+            return;
+        }
+
+        let old_offsets = paragraph_offsets(&self.paragraph_texts);
+        let new_offsets = paragraph_offsets(new_paragraphs);
+        let mut moved_ids = BTreeMap::new();
+        for (old_id, old_offset) in self.paragraph_ids.iter().zip(old_offsets) {
+            if new_ids.contains(old_id) {
+                continue;
+            }
+
+            let new_num = new_offsets
+                .partition_point(|&new_offset| new_offset <= old_offset)
+                .saturating_sub(1);
+            if let Some(new_id) = new_ids.get(new_num) {
+                moved_ids.insert(old_id.clone(), new_id.clone());
+            }
+        }
+
+        for old_id in &self.paragraph_ids {
+            let Some(new_id) = moved_ids.get(old_id) else {
+                continue;
+            };
+            let Some(note) = self.notes.remove(old_id) else {
+                continue;
+            };
+
+            self.notes
+                .entry(new_id.clone())
+                .and_modify(|new_note| {
+                    new_note.push_str("\n\n");
+                    new_note.push_str(&note);
+                })
+                .or_insert(note);
+        }
+        for paragraph_id in self.bookmarks.values_mut() {
+            if let Some(new_id) = moved_ids.get(paragraph_id) {
+                *paragraph_id = new_id.clone();
+            }
+        }
+    }
+
+    /// Keeps track of the paragraphs of a text that may have been edited
+    /// since it was last opened. Recordings of paragraphs that are still
+    /// there follow them to wherever they are now, including ones with a
+    /// few words changed, which are marked for a retake. Returns the
+    /// positions of the new and changed paragraphs.
+    pub fn realign_paragraphs(&mut self, paragraphs: &[String]) -> Vec<usize> {
+        let new_ids = paragraph_ids(paragraphs);
+        if self.paragraph_texts.is_empty() || new_ids == self.paragraph_ids {
+            self.set_paragraphs(paragraphs);
+            return Vec::new();
+        }
+
+        let mut realigned_recordings = BTreeMap::new();
+        let mut realigned_statuses = BTreeMap::new();
+        let mut realigned_notes = BTreeMap::new();
+        let mut realigned_ids = BTreeMap::new();
+        let mut flagged_paragraphs = Vec::new();
+        let origins = match_paragraphs(&self.paragraph_texts, paragraphs);
+        for (paragraph_num, (origin, new_id)) in origins.into_iter().zip(&new_ids).enumerate() {
+            let old_id = match origin {
+                Origin::Unchanged(old_num) | Origin::Changed(old_num) => {
+                    self.paragraph_ids.get(old_num).cloned()
+                }
+                Origin::New => None,
+            };
+
+            let takes = old_id
+                .as_ref()
+                .and_then(|old_id| self.recordings.remove(old_id));
+            let status = old_id
+                .as_ref()
+                .and_then(|old_id| self.statuses.remove(old_id));
+
+            match origin {
+                Origin::Unchanged(_) => {
+                    if let Some(status) = status {
+                        realigned_statuses.insert(new_id.clone(), status);
+                    }
+                }
+                _ => {
+                    // Its takes are readings of what it used to say
+                    if takes.is_some() {
+                        realigned_statuses.insert(new_id.clone(), ParagraphStatus::Retake);
+                    }
+                    flagged_paragraphs.push(paragraph_num);
+                }
+            }
+            if let Some(takes) = takes {
+                realigned_recordings.insert(new_id.clone(), takes);
+            }
+
+            if let Some(old_id) = old_id {
+                if let Some(note) = self.notes.remove(&old_id) {
+                    realigned_notes.insert(new_id.clone(), note);
+                }
+                realigned_ids.insert(old_id, new_id.clone());
+            }
+        }
+        self.recordings.extend(realigned_recordings);
+        self.statuses.extend(realigned_statuses);
+        self.notes.extend(realigned_notes);
+        let chapter_ids = self
+            .chapters
+            .iter_mut()
+            .map(|chapter_start| &mut chapter_start.first_paragraph_id);
+        for paragraph_id in self.bookmarks.values_mut().chain(chapter_ids) {
+            if let Some(new_id) = realigned_ids.get(paragraph_id) {
+                *paragraph_id = new_id.clone();
+            }
+        }
+
+        self.set_paragraphs(paragraphs);
+        flagged_paragraphs
+    }
+
+    /// Returns the recordings whose paragraph isn't in the text the way
+    /// it's gathered now, by the ID of that paragraph.
+    pub fn unmatched_recordings(&self) -> BTreeMap<&str, &Takes> {
+        self.recordings
+            .iter()
+            .filter(|(paragraph_id, takes)| {
+                !takes.is_empty() && !self.paragraph_ids.contains(paragraph_id)
+            })
+            .map(|(paragraph_id, takes)| (paragraph_id.as_str(), takes))
+            .collect()
+    }
+
+    fn paragraph_id(&self, paragraph_num: usize) -> Option<&String> {
+        self.paragraph_ids.get(paragraph_num)
+    }
+
+    /// Returns where the paragraph with the ID is, if it's in the text the
+    /// way it's gathered now.
+    fn paragraph_position(&self, paragraph_id: &str) -> Option<usize> {
+        self.paragraph_ids
+            .iter()
+            .position(|other_id| other_id == paragraph_id)
+    }
+
+    pub fn takes(&self, paragraph_num: usize) -> Option<&Takes> {
+        self.paragraph_id(paragraph_num)
+            .and_then(|paragraph_id| self.recordings.get(paragraph_id))
+    }
+
+    /// Returns where a new take of the paragraph should be recorded to,
+    /// without registering it until recording has started, or None if
+    /// there's no such paragraph to record.
+    pub fn new_take_location(&self, paragraph_num: usize) -> Option<PathBuf> {
+        self.paragraph_id(paragraph_num)?;
+
+        let project_directory = self.project_directory();
+        let takes = self.takes(paragraph_num);
+        let is_taken = |file_name: &str| {
+            takes.is_some_and(|takes| takes.contains(file_name))
+                || project_directory.join(file_name).exists()
+        };
+
+        let mut take_num = takes.map_or(0, Takes::len) + 1;
+        let mut take_file_name = format!("part{paragraph_num}_take{take_num}.wav");
+        while is_taken(&take_file_name) {
+            take_num += 1;
+            take_file_name = format!("part{paragraph_num}_take{take_num}.wav");
+        }
+
+        Some(project_directory.join(take_file_name))
+    }
+
+    /// Registers the take being recorded to the location as a new take
+    /// for the paragraph. The new take becomes the active one.
+    pub fn add_take(&mut self, paragraph_num: usize, take_location: &Path) {
+        let Some(paragraph_id) = self.paragraph_id(paragraph_num).cloned() else {
+            return;
+        };
+        let take_file_name = take_location
+            .file_name()
+            .expect("Takes should be recorded to a file.")
+            .to_string_lossy()
+            .into_owned();
+
+        // Recording again settles a retake, and needs approving again
+        self.statuses.remove(&paragraph_id);
+        self.recordings
+            .entry(paragraph_id)
+            .or_default()
+            .add(take_file_name);
+    }
+
+    /// Returns where the paragraph is at in being recorded, going by
+    /// whether it has any takes unless it was marked otherwise. There's
+    /// nothing to approve without a take, so it's unrecorded until then.
+    pub fn status(&self, paragraph_num: usize) -> ParagraphStatus {
+        let is_recorded = self
+            .takes(paragraph_num)
+            .is_some_and(|takes| !takes.is_empty());
+        let marked_status = self
+            .paragraph_id(paragraph_num)
+            .and_then(|paragraph_id| self.statuses.get(paragraph_id))
+            .filter(|&&status| is_recorded || status != ParagraphStatus::Approved);
+        if let Some(status) = marked_status {
+            return *status;
+        }
+
+        if is_recorded {
+            ParagraphStatus::Recorded
+        } else {
+            ParagraphStatus::Unrecorded
+        }
+    }
+
+    /// Marks the paragraph with a status, or with None, goes back to
+    /// going by whether it has any takes.
+    pub fn set_status(&mut self, paragraph_num: usize, status: Option<ParagraphStatus>) {
+        let Some(paragraph_id) = self.paragraph_id(paragraph_num).cloned() else {
+            return;
+        };
+
+        match status {
+            Some(status) => {
+                self.statuses.insert(paragraph_id, status);
+            }
+            None => {
+                self.statuses.remove(&paragraph_id);
+            }
+        }
+    }
+
+    /// Returns the note left on the paragraph, which is empty if there's none.
+    pub fn note(&self, paragraph_num: usize) -> String {
+        self.paragraph_id(paragraph_num)
+            .and_then(|paragraph_id| self.notes.get(paragraph_id))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn set_note(&mut self, paragraph_num: usize, note: &str) {
+        let Some(paragraph_id) = self.paragraph_id(paragraph_num).cloned() else {
+            return;
+        };
+
+        if note.trim().is_empty() {
+            self.notes.remove(&paragraph_id);
+        } else {
+            self.notes.insert(paragraph_id, String::from(note));
+        }
+    }
+
+    /// Returns the names of the bookmarks, in alphabetical order.
+    pub fn bookmark_names(&self) -> Vec<String> {
+        self.bookmarks.keys().cloned().collect()
+    }
+
+    /// Bookmarks the paragraph, replacing any bookmark by the same name.
+    pub fn add_bookmark(&mut self, name: &str, paragraph_num: usize) {
+        if let Some(paragraph_id) = self.paragraph_id(paragraph_num).cloned() {
+            self.bookmarks.insert(String::from(name), paragraph_id);
+        }
+    }
+
+    pub fn remove_bookmark(&mut self, name: &str) {
+        self.bookmarks.remove(name);
+    }
+
+    /// Returns where the bookmarked paragraph is, or None if it isn't in
+    /// the text the way it's gathered now.
+    pub fn bookmark(&self, name: &str) -> Option<usize> {
+        self.paragraph_position(self.bookmarks.get(name)?)
+    }
+
+    /// Finds the closest paragraph after the given one with the status.
+    pub fn next_with_status(&self, paragraph_num: usize, status: ParagraphStatus) -> Option<usize> {
+        (paragraph_num + 1..self.paragraph_ids.len())
+            .find(|&other_num| self.status(other_num) == status)
+    }
+
+    /// Finds the closest paragraph before the given one with the status.
+    pub fn previous_with_status(
+        &self,
+        paragraph_num: usize,
+        status: ParagraphStatus,
+    ) -> Option<usize> {
+        (0..paragraph_num.min(self.paragraph_ids.len()))
+            .rev()
+            .find(|&other_num| self.status(other_num) == status)
+    }
+
+    pub fn set_active_take(&mut self, paragraph_num: usize, take_num: usize) {
+        let Some(paragraph_id) = self.paragraph_id(paragraph_num).cloned() else {
+            return;
+        };
+
+        if let Some(takes) = self.recordings.get_mut(&paragraph_id) {
+            takes.set_active(take_num);
+        }
+    }
+
+    /// Returns the location of the take chosen for playback and export,
+    /// if the paragraph has been recorded.
+    pub fn active_take(&self, paragraph_num: usize) -> Option<PathBuf> {
+        self.takes(paragraph_num)
+            .and_then(|takes| takes.active_file_name())
+            .map(|file_name| self.project_directory().join(file_name))
+    }
+
+    /// Recordings made before takes existed were saved as part{N}.wav,
+    /// so those are brought in as the first take of their paragraph.
+    fn adopt_legacy_recordings(&mut self) {
+        let project_directory = self.project_directory();
+        let adopted_file_names: BTreeSet<String> = self
+            .recordings
+            .values()
+            .flat_map(|takes| takes.file_names())
+            .cloned()
+            .collect();
+
+        for (paragraph_num, paragraph_id) in self.paragraph_ids.iter().enumerate() {
+            let legacy_file_name = format!("part{paragraph_num}.wav");
+            let is_recorded = self
+                .recordings
+                .get(paragraph_id)
+                .is_some_and(|takes| !takes.is_empty());
+            if is_recorded
+                || adopted_file_names.contains(&legacy_file_name)
+                || !project_directory.join(&legacy_file_name).is_file()
+            {
+                continue;
+            }
+
+            self.recordings
+                .entry(paragraph_id.clone())
+                .or_default()
+                .add(legacy_file_name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paragraphs(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|text| String::from(*text)).collect()
+    }
+
+    fn test_session() -> Session {
+        Session::with_project_directory(String::from("book"), std::env::temp_dir())
+    }
+
+    #[test]
+    fn legacy_recordings_are_adopted_as_first_gathered() {
+        let project_directory = tempfile::tempdir().expect("Could not create temporary directory.");
+        for legacy_file_name in ["part0.wav", "part2.wav"] {
+            File::create(project_directory.path().join(legacy_file_name))
+                .expect("Could not create legacy recording.");
+        }
+        let mut session = Session::with_project_directory(
+            String::from("book"),
+            project_directory.path().to_path_buf(),
+        );
+
+        session.set_paragraphs(&paragraphs(&["One. Two. ", "Three. Four. ", "Five. Six."]));
+        assert_eq!(
+            session.active_take(0),
+            Some(project_directory.path().join("part0.wav"))
+        );
+        assert_eq!(session.active_take(1), None);
+        assert_eq!(
+            session.active_take(2),
+            Some(project_directory.path().join("part2.wav"))
+        );
+
+        // Split differently, they aren't paired up again by position
+        session.set_paragraphs(&paragraphs(&["One. ", "Two. ", "Three. Four. Five. Six."]));
+        assert_eq!(session.active_take(0), None);
+        assert_eq!(session.active_take(2), None);
+        assert_eq!(session.unmatched_recordings().len(), 2);
+    }
+
+    fn recorded_session(texts: &[&str]) -> Session {
+        let mut session = test_session();
+        session.set_paragraphs(&paragraphs(texts));
+        for paragraph_num in 0..texts.len() {
+            let take_location = session
+                .new_take_location(paragraph_num)
+                .expect("Every paragraph should be recordable.");
+            session.add_take(paragraph_num, &take_location);
+        }
+
+        session
+    }
+
+    #[test]
+    fn realigning_follows_an_inserted_paragraph() {
+        let mut session = recorded_session(&["One. ", "Two. ", "Three. "]);
+        session.set_status(1, Some(ParagraphStatus::Approved));
+        session.set_note(2, "Slower here.");
+        session.add_bookmark("Ending", 2);
+        let third_take = session.active_take(2);
+
+        let flagged_paragraphs =
+            session.realign_paragraphs(&paragraphs(&["One. ", "A new one. ", "Two. ", "Three. "]));
+
+        assert_eq!(flagged_paragraphs, [1]);
+        assert_eq!(session.takes(1), None);
+        assert_eq!(session.status(1), ParagraphStatus::Unrecorded);
+        assert_eq!(session.status(2), ParagraphStatus::Approved);
+        assert_eq!(session.active_take(3), third_take);
+        assert_eq!(session.note(3), "Slower here.");
+        assert_eq!(session.bookmark("Ending"), Some(3));
+        assert!(session.unmatched_recordings().is_empty());
+    }
+
+    #[test]
+    fn realigning_marks_an_edited_paragraph_for_a_retake() {
+        let mut session = recorded_session(&["It was a dark and stormy night. ", "The end. "]);
+        session.set_status(0, Some(ParagraphStatus::Approved));
+        session.set_note(0, "Check the pronunciation.");
+        let first_take = session.active_take(0);
+
+        let flagged_paragraphs = session.realign_paragraphs(&paragraphs(&[
+            "It was a dark and stormy evening. ",
+            "The end. ",
+        ]));
+
+        assert_eq!(flagged_paragraphs, [0]);
+        assert_eq!(session.active_take(0), first_take);
+        assert_eq!(session.status(0), ParagraphStatus::Retake);
+        assert_eq!(session.note(0), "Check the pronunciation.");
+        assert_eq!(session.status(1), ParagraphStatus::Recorded);
+    }
+
+    #[test]
+    fn resplitting_moves_notes_and_bookmarks_along() {
+        let mut session = test_session();
+        session.set_paragraphs(&paragraphs(&["One. ", "Two. ", "Three. ", "Four. "]));
+        session.set_note(0, "Warmly.");
+        session.set_note(1, "Pause after.");
+        session.set_note(3, "Slower here.");
+        session.add_bookmark("Middle", 2);
+
+        session.set_paragraphs(&paragraphs(&["One. Two. ", "Three. Four. "]));
+
+        assert_eq!(session.note(0), "Warmly.\n\nPause after.");
+        assert_eq!(session.note(1), "Slower here.");
+        assert_eq!(session.bookmark("Middle"), Some(1));
+
+        session.set_paragraphs(&paragraphs(&["One. ", "Two. ", "Three. ", "Four. "]));
+
+        assert_eq!(session.note(0), "Warmly.\n\nPause after.");
+        assert_eq!(session.note(1), "");
+        assert_eq!(session.note(2), "Slower here.");
+        assert_eq!(session.bookmark("Middle"), Some(2));
+    }
+
+    #[test]
+    fn chapters_follow_their_first_paragraph() {
+        let mut session = test_session();
+        session.set_paragraphs(&paragraphs(&[
+            "CHAPTER I\nOne. ",
+            "Two. ",
+            "CHAPTER II\nThree. ",
+        ]));
+        session.set_chapters(&[
+            Chapter {
+                title: Some(String::from("CHAPTER I")),
+                first_paragraph: 0,
+                num_paragraphs: 2,
+            },
+            Chapter {
+                title: Some(String::from("CHAPTER II")),
+                first_paragraph: 2,
+                num_paragraphs: 1,
+            },
+            Chapter {
+                title: Some(String::from("CHAPTER III")),
+                first_paragraph: 3,
+                num_paragraphs: 0,
+            },
+        ]);
+
+        // A paragraph written into the first chapter moves the second one along
+        session.realign_paragraphs(&paragraphs(&[
+            "CHAPTER I\nOne. ",
+            "Two. ",
+            "Two and a half. ",
+            "CHAPTER II\nThree. ",
+        ]));
+        let chapters = session.chapters();
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[0].num_paragraphs, 3);
+        assert_eq!(chapters[1].title.as_deref(), Some("CHAPTER II"));
+        assert_eq!(chapters[1].first_paragraph, 3);
+        assert_eq!(chapters[1].num_paragraphs, 1);
+
+        let saved = serde_json::to_string(&session).expect("Could not save session.");
+        let loaded: Session = serde_json::from_str(&saved).expect("Could not load session.");
+        assert_eq!(loaded.chapters(), chapters);
+    }
 }
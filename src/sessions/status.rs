@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/// Where a paragraph is at in being read for the audiobook.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ParagraphStatus {
+    Unrecorded,
+    Recorded,
+    /// Recorded, but needing to be read again.
+    Retake,
+    /// Recorded, and the reading is a keeper.
+    Approved,
+}
+
+impl ParagraphStatus {
+    /// Every status, in the order they're listed in.
+    pub const ALL: [ParagraphStatus; 4] = [
+        ParagraphStatus::Unrecorded,
+        ParagraphStatus::Recorded,
+        ParagraphStatus::Retake,
+        ParagraphStatus::Approved,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ParagraphStatus::Unrecorded => "Unrecorded",
+            ParagraphStatus::Recorded => "Recorded",
+            ParagraphStatus::Retake => "Retake",
+            ParagraphStatus::Approved => "Approved",
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+/// All recordings made for a single paragraph, along with which one
+/// is used for playback and export.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct Takes {
+    file_names: Vec<String>,
+    active_take: usize,
+}
+
+impl Takes {
+    /// Adds a new take by its file name, making it the active one.
+    pub fn add(&mut self, file_name: String) {
+        self.file_names.push(file_name);
+        self.active_take = self.file_names.len() - 1;
+    }
+
+    pub fn file_names(&self) -> &[String] {
+        &self.file_names
+    }
+
+    pub fn contains(&self, file_name: &str) -> bool {
+        self.file_names.iter().any(|take| take == file_name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.file_names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.file_names.is_empty()
+    }
+
+    /// Changes the active take, ignoring positions that do not exist.
+    pub fn set_active(&mut self, take_num: usize) {
+        if take_num < self.file_names.len() {
+            self.active_take = take_num;
+        }
+    }
+
+    pub fn active(&self) -> usize {
+        self.active_take
+    }
+
+    pub fn active_file_name(&self) -> Option<&str> {
+        self.file_names.get(self.active_take).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_take_becomes_active() {
+        let mut takes = Takes::default();
+        assert!(takes.active_file_name().is_none());
+
+        takes.add(String::from("part0_take1.wav"));
+        takes.add(String::from("part0_take2.wav"));
+
+        assert_eq!(takes.len(), 2);
+        assert_eq!(takes.active(), 1);
+        assert_eq!(takes.active_file_name(), Some("part0_take2.wav"));
+    }
+
+    #[test]
+    fn set_active_within_bounds() {
+        let mut takes = Takes::default();
+        takes.add(String::from("part0_take1.wav"));
+        takes.add(String::from("part0_take2.wav"));
+
+        takes.set_active(0);
+        assert_eq!(takes.active_file_name(), Some("part0_take1.wav"));
+
+        takes.set_active(5);
+        assert_eq!(takes.active_file_name(), Some("part0_take1.wav"));
+    }
+}
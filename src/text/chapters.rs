@@ -0,0 +1,257 @@
+use regex::Regex;
+
+/// Headings like "CHAPTER IV", "Chapter 12: The Storm", or "BOOK ONE".
+const NUMBERED_HEADING_PATTERN: &str = r"^\s*(?i:chapter|book|part)\s+([0-9]+|[IVXLCDM]+|(?i:one|two|three|four|five|six|seven|eight|nine|ten|eleven|twelve|thirteen|fourteen|fifteen|sixteen|seventeen|eighteen|nineteen|twenty|thirty|forty|fifty)(?:[- ](?i:one|two|three|four|five|six|seven|eight|nine))?)\s*([.:\-–—]\s*\S.*)?\s*$";
+
+/// Markdown headings, such as "# Chapter Title".
+const MARKDOWN_HEADING_PATTERN: &str = r"^\s*#{1,6}\s+\S.*$";
+
+/// Headings longer than this are much more likely to be a sentence.
+const MAX_HEADING_LENGTH: usize = 80;
+
+/// A chapter of the loaded text and the paragraphs gathered from it.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Chapter {
+    /// The heading starting the chapter, or None for any text before the first heading.
+    pub title: Option<String>,
+    pub first_paragraph: usize,
+    pub num_paragraphs: usize,
+}
+
+impl Chapter {
+    pub fn contains(&self, paragraph_num: usize) -> bool {
+        (self.first_paragraph..self.first_paragraph + self.num_paragraphs).contains(&paragraph_num)
+    }
+}
+
+/// Where a chapter starts in text whose headings are already known,
+/// like a book's table of contents.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct ChapterBreak {
+    /// Byte offset of the chapter's start in the text.
+    pub offset: usize,
+    pub title: String,
+}
+
+/// Splits the text at each of the breaks, which have to be in order.
+/// Like ChapterDetector::split, joining the texts gives back the original.
+pub fn split_at_breaks<'a>(text: &'a str, breaks: &[ChapterBreak]) -> Vec<ChapterText<'a>> {
+    let mut chapters = Vec::new();
+
+    let first_break = breaks.first().map_or(text.len(), |first| first.offset);
+    if !text[..first_break].trim().is_empty() {
+        chapters.push(ChapterText {
+            title: None,
+            text: &text[..first_break],
+        });
+    }
+
+    for (break_num, chapter_break) in breaks.iter().enumerate() {
+        let chapter_start = if chapters.is_empty() {
+            0
+        } else {
+            chapter_break.offset
+        };
+        let chapter_end = breaks
+            .get(break_num + 1)
+            .map_or(text.len(), |next_break| next_break.offset);
+
+        chapters.push(ChapterText {
+            title: Some(chapter_break.title.clone()),
+            text: &text[chapter_start..chapter_end],
+        });
+    }
+
+    chapters
+}
+
+/// Text belonging to one chapter, heading included.
+#[derive(PartialEq, Eq, Debug)]
+pub struct ChapterText<'a> {
+    pub title: Option<String>,
+    pub text: &'a str,
+}
+
+/// Finds lines in the text that start a new chapter.
+pub struct ChapterDetector {
+    heading_patterns: Vec<Regex>,
+}
+
+impl Default for ChapterDetector {
+    fn default() -> Self {
+        ChapterDetector::new("").expect("Built-in chapter heading patterns should be valid.")
+    }
+}
+
+impl ChapterDetector {
+    /// Creates a detector recognizing common chapter headings, as well as
+    /// lines matching the custom pattern, unless it's empty.
+    pub fn new(custom_pattern: &str) -> Result<ChapterDetector, regex::Error> {
+        let mut heading_patterns = vec![
+            Regex::new(NUMBERED_HEADING_PATTERN)?,
+            Regex::new(MARKDOWN_HEADING_PATTERN)?,
+        ];
+
+        if !custom_pattern.is_empty() {
+            heading_patterns.push(Regex::new(custom_pattern)?);
+        }
+
+        Ok(ChapterDetector { heading_patterns })
+    }
+
+    pub fn is_heading(&self, line: &str) -> bool {
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        !line.trim().is_empty()
+            && line.len() <= MAX_HEADING_LENGTH
+            && self
+                .heading_patterns
+                .iter()
+                .any(|pattern| pattern.is_match(line))
+    }
+
+    /// Splits the text at every heading, so nothing in one chapter ends
+    /// up with another. Joining the texts back gives the original text.
+    pub fn split<'a>(&self, text: &'a str) -> Vec<ChapterText<'a>> {
+        let mut chapters = Vec::new();
+        let mut chapter_start = 0;
+        let mut chapter_title = None;
+
+        let mut line_start = 0;
+        for line in text.split_inclusive('\n') {
+            if self.is_heading(line) {
+                if !text[chapter_start..line_start].trim().is_empty() || chapter_title.is_some() {
+                    chapters.push(ChapterText {
+                        title: chapter_title.take(),
+                        text: &text[chapter_start..line_start],
+                    });
+                    chapter_start = line_start;
+                }
+
+                chapter_title = Some(line.trim().to_string());
+            }
+
+            line_start += line.len();
+        }
+
+        if chapter_start < text.len() || chapter_title.is_some() {
+            chapters.push(ChapterText {
+                title: chapter_title,
+                text: &text[chapter_start..],
+            });
+        }
+
+        chapters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_common_headings() {
+        let detector = ChapterDetector::default();
+
+        assert!(detector.is_heading("CHAPTER IV\n"));
+        assert!(detector.is_heading("Chapter 12: The Storm"));
+        assert!(detector.is_heading("BOOK ONE"));
+        assert!(detector.is_heading("Part Twenty-One"));
+        assert!(detector.is_heading("## The Return\r\n"));
+
+        assert!(!detector.is_heading("Part of me wanted to leave."));
+        assert!(!detector.is_heading("Chapter and verse were quoted at length."));
+        assert!(!detector.is_heading("#hashtag"));
+        assert!(!detector.is_heading(""));
+    }
+
+    #[test]
+    fn custom_pattern_adds_headings() {
+        let detector = ChapterDetector::new(r"^\*\*\*$").unwrap();
+
+        assert!(detector.is_heading("***\n"));
+        assert!(detector.is_heading("CHAPTER I"));
+        assert!(ChapterDetector::new("(").is_err());
+    }
+
+    #[test]
+    fn splits_text_at_headings() {
+        let text = "Preface text.\n\nCHAPTER I\nIt begins.\n\nCHAPTER II\nIt ends.\n";
+        let chapters = ChapterDetector::default().split(text);
+
+        assert_eq!(
+            chapters,
+            [
+                ChapterText {
+                    title: None,
+                    text: "Preface text.\n\n"
+                },
+                ChapterText {
+                    title: Some(String::from("CHAPTER I")),
+                    text: "CHAPTER I\nIt begins.\n\n"
+                },
+                ChapterText {
+                    title: Some(String::from("CHAPTER II")),
+                    text: "CHAPTER II\nIt ends.\n"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn text_without_headings_is_one_chapter() {
+        let text = "Just some sentences. Nothing else.";
+
+        assert_eq!(
+            ChapterDetector::default().split(text),
+            [ChapterText { title: None, text }]
+        );
+        assert!(ChapterDetector::default().split("").is_empty());
+    }
+
+    #[test]
+    fn leading_blank_lines_join_first_chapter() {
+        let chapters = ChapterDetector::default().split("\n\n# One\nText.");
+
+        assert_eq!(chapters.len(), 1);
+        assert_eq!(chapters[0].text, "\n\n# One\nText.");
+    }
+
+    #[test]
+    fn splits_text_at_known_breaks() {
+        let text = "Cover\n\nOne\nText.\n\nTwo\nMore.";
+        let breaks = [
+            ChapterBreak {
+                offset: 7,
+                title: String::from("One"),
+            },
+            ChapterBreak {
+                offset: 18,
+                title: String::from("Two"),
+            },
+        ];
+
+        let chapters = split_at_breaks(text, &breaks);
+        assert_eq!(chapters.len(), 3);
+        assert_eq!(chapters[0].text, "Cover\n\n");
+        assert_eq!(chapters[1].text, "One\nText.\n\n");
+        assert_eq!(chapters[2].title.as_deref(), Some("Two"));
+        assert_eq!(
+            chapters
+                .iter()
+                .map(|chapter| chapter.text)
+                .collect::<String>(),
+            text
+        );
+
+        // Blank text before the first break joins the first chapter
+        let leading_blank_breaks = [ChapterBreak {
+            offset: 1,
+            title: String::from("One"),
+        }];
+        assert_eq!(
+            split_at_breaks("\nOne", &leading_blank_breaks)[0].text,
+            "\nOne"
+        );
+    }
+}
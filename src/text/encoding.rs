@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+
+/// Encodings offered when the one worked out for a file is wrong.
+pub const TEXT_ENCODINGS: [&str; 14] = [
+    "UTF-8",
+    "UTF-16LE",
+    "UTF-16BE",
+    "windows-1252",
+    "ISO-8859-15",
+    "ISO-8859-2",
+    "windows-1250",
+    "windows-1251",
+    "KOI8-R",
+    "Shift_JIS",
+    "EUC-JP",
+    "GBK",
+    "Big5",
+    "EUC-KR",
+];
+
+/// Reads a text file in the given encoding, or otherwise in the one worked
+/// out from its contents.
+pub fn read_text(text_file: &Path, encoding_label: Option<&str>) -> Result<String> {
+    Ok(decode_text(&std::fs::read(text_file)?, encoding_label))
+}
+
+/// Finds the encoding going by one of its names, like "windows-1252".
+pub fn encoding_for_label(encoding_label: &str) -> Result<&'static Encoding> {
+    match Encoding::for_label(encoding_label.as_bytes()) {
+        Some(encoding) => Ok(encoding),
+        None => bail!("\"{encoding_label}\" is not a text encoding that can be read."),
+    }
+}
+
+/// Turns the bytes of a text into a string. Without an encoding to use,
+/// or with one that isn't known, it's taken from a byte order mark, or
+/// guessed at from what the bytes look like. Anything that can't be
+/// decoded becomes a replacement character.
+pub fn decode_text(bytes: &[u8], encoding_label: Option<&str>) -> String {
+    match encoding_label.and_then(|encoding_label| encoding_for_label(encoding_label).ok()) {
+        Some(encoding) => encoding.decode_with_bom_removal(bytes).0.into_owned(),
+        None => detect_encoding(bytes).decode(bytes).0.into_owned(),
+    }
+}
+
+/// Works out which encoding the text is most likely in.
+fn detect_encoding(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+
+    // Mostly Latin UTF-16 is also valid UTF-8, just full of zero bytes
+    if let Some(encoding) = detect_utf16(bytes) {
+        return encoding;
+    }
+
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    detector.guess(None, true)
+}
+
+/// Spots UTF-16 without a byte order mark by the zero bytes that mostly
+/// Latin text leaves on one side of every pair.
+fn detect_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let pairs: Vec<&[u8]> = bytes.chunks_exact(2).take(4096).collect();
+    if pairs.is_empty() {
+        return None;
+    }
+
+    let num_even_zeros = pairs.iter().filter(|pair| pair[0] == 0).count();
+    let num_odd_zeros = pairs.iter().filter(|pair| pair[1] == 0).count();
+    let is_mostly_zeros = |num_zeros: usize| num_zeros * 10 >= pairs.len() * 4;
+    let is_hardly_zeros = |num_zeros: usize| num_zeros * 20 <= pairs.len();
+
+    if is_mostly_zeros(num_odd_zeros) && is_hardly_zeros(num_even_zeros) {
+        Some(UTF_16LE)
+    } else if is_mostly_zeros(num_even_zeros) && is_hardly_zeros(num_odd_zeros) {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    #[test]
+    fn reads_byte_order_marks() {
+        let mut with_bom = vec![0xFF, 0xFE];
+        with_bom.extend(utf16le("Café"));
+        assert_eq!(decode_text(&with_bom, None), "Café");
+
+        assert_eq!(decode_text(b"\xEF\xBB\xBFPlain.", None), "Plain.");
+    }
+
+    #[test]
+    fn guesses_legacy_encodings() {
+        let windows_1252 = b"\x93It\x92s a caf\xe9 na\xefve r\xe9sum\xe9,\x94 she said.";
+        assert_eq!(
+            decode_text(windows_1252, None),
+            "“It’s a café naïve résumé,” she said."
+        );
+
+        assert_eq!(
+            decode_text(&utf16le("No byte order mark here."), None),
+            "No byte order mark here."
+        );
+    }
+
+    #[test]
+    fn uses_chosen_encoding() {
+        assert_eq!(
+            decode_text(b"\xc8\xe2\xe0\xed", Some("windows-1251")),
+            "Иван"
+        );
+
+        for label in TEXT_ENCODINGS {
+            assert!(encoding_for_label(label).is_ok(), "{label}");
+        }
+    }
+
+    #[test]
+    fn unknown_encoding_is_worked_out() {
+        assert!(encoding_for_label("not an encoding").is_err());
+        assert_eq!(decode_text(b"caf\xc3\xa9", Some("not an encoding")), "café");
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use zip::ZipArchive;
+
+use crate::text::chapters::ChapterBreak;
+use crate::text::markup::{attribute, decode_entities, html_to_text, local_name, tokens, Token};
+use crate::text::source::{read_archive_entry, SourceOptions, SourceText};
+
+struct ManifestItem {
+    href: String,
+    media_type: String,
+    properties: String,
+}
+
+/// Reads the text of an EPUB book in the reading order given by its
+/// package's spine, with chapters named after its table of contents.
+pub fn read_epub(epub_file: &Path, options: &SourceOptions) -> Result<SourceText> {
+    let mut archive = ZipArchive::new(File::open(epub_file)?)?;
+
+    let container = read_archive_entry(&mut archive, "META-INF/container.xml")?;
+    let Some(package_path) = tokens(&container).find_map(|token| match token {
+        Token::StartTag {
+            name, attributes, ..
+        } if local_name(name) == "rootfile" => attribute(attributes, "full-path"),
+        _ => None,
+    }) else {
+        bail!("The EPUB's container does not point to a package document.");
+    };
+
+    let package = read_archive_entry(&mut archive, &package_path)?;
+    let package_dir = parent_dir(&package_path);
+
+    let mut manifest = HashMap::new();
+    let mut spine = Vec::new();
+    let mut ncx_id = None;
+    for token in tokens(&package) {
+        let Token::StartTag {
+            name, attributes, ..
+        } = token
+        else {
+            continue;
+        };
+
+        match local_name(name) {
+            "item" => {
+                if let (Some(id), Some(href)) =
+                    (attribute(attributes, "id"), attribute(attributes, "href"))
+                {
+                    manifest.insert(
+                        id,
+                        ManifestItem {
+                            href: resolve_href(package_dir, &href),
+                            media_type: attribute(attributes, "media-type").unwrap_or_default(),
+                            properties: attribute(attributes, "properties").unwrap_or_default(),
+                        },
+                    );
+                }
+            }
+            "spine" => ncx_id = attribute(attributes, "toc"),
+            "itemref" if attribute(attributes, "linear").as_deref() != Some("no") => {
+                if let Some(idref) = attribute(attributes, "idref") {
+                    spine.push(idref);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let nav_item = manifest.values().find(|item| {
+        item.properties
+            .split_whitespace()
+            .any(|property| property == "nav")
+    });
+    let chapter_titles = match (nav_item, ncx_id.and_then(|id| manifest.get(&id))) {
+        (Some(nav_item), _) => {
+            let nav = read_archive_entry(&mut archive, &nav_item.href)?;
+            nav_document_titles(&nav, parent_dir(&nav_item.href))
+        }
+        (None, Some(ncx_item)) => {
+            let ncx = read_archive_entry(&mut archive, &ncx_item.href)?;
+            ncx_titles(&ncx, parent_dir(&ncx_item.href))
+        }
+        (None, None) => HashMap::new(),
+    };
+
+    let mut source_text = SourceText::default();
+    for idref in spine {
+        let Some(item) = manifest.get(&idref) else {
+            continue;
+        };
+        if !matches!(
+            item.media_type.as_str(),
+            "application/xhtml+xml" | "text/html"
+        ) {
+            continue;
+        }
+
+        let document = read_archive_entry(&mut archive, &item.href)?;
+        let document_text = html_to_text(&document, options.read_code_blocks).text;
+        if document_text.is_empty() {
+            continue;
+        }
+
+        if !source_text.text.is_empty() {
+            source_text.text.push_str("\n\n");
+        }
+
+        if let Some(title) = chapter_titles.get(&item.href) {
+            source_text.chapter_breaks.push(ChapterBreak {
+                offset: source_text.text.len(),
+                title: title.clone(),
+            });
+        }
+
+        source_text.text.push_str(&document_text);
+    }
+
+    Ok(source_text)
+}
+
+/// Returns the directory of a path within the archive, with a trailing slash.
+fn parent_dir(entry_name: &str) -> &str {
+    entry_name
+        .rfind('/')
+        .map_or("", |slash| &entry_name[..slash + 1])
+}
+
+/// Turns a link relative to a document into a path within the archive,
+/// dropping any fragment.
+fn resolve_href(base_dir: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+    let href = percent_decode(href);
+
+    let mut segments: Vec<&str> = base_dir
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+
+    segments.join("/")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut position = 0;
+    while position < bytes.len() {
+        let escaped_byte = (bytes[position] == b'%')
+            .then(|| text.get(position + 1..position + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escaped_byte {
+            Some(byte) => {
+                decoded.push(byte);
+                position += 3;
+            }
+            None => {
+                decoded.push(bytes[position]);
+                position += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Reads the chapter titles of an EPUB 3 navigation document's table of
+/// contents, keyed by the document each one starts in.
+fn nav_document_titles(nav: &str, nav_dir: &str) -> HashMap<String, String> {
+    let mut titles = HashMap::new();
+    let mut in_toc = false;
+    let mut current_link: Option<(String, String)> = None;
+
+    for token in tokens(nav) {
+        match token {
+            Token::StartTag {
+                name, attributes, ..
+            } => match local_name(name) {
+                "nav" => {
+                    // Books without a labelled table of contents only have the one nav
+                    in_toc =
+                        attribute(attributes, "epub:type").map_or(titles.is_empty(), |nav_type| {
+                            nav_type
+                                .split_whitespace()
+                                .any(|nav_type| nav_type == "toc")
+                        });
+                }
+                "a" if in_toc => {
+                    current_link = attribute(attributes, "href")
+                        .map(|href| (resolve_href(nav_dir, &href), String::new()));
+                }
+                _ => {}
+            },
+            Token::EndTag { name } => match local_name(name) {
+                "nav" => in_toc = false,
+                "a" => {
+                    if let Some((document, title)) = current_link.take() {
+                        add_title(&mut titles, document, &title);
+                    }
+                }
+                _ => {}
+            },
+            Token::Text(text) => {
+                if let Some((_, title)) = current_link.as_mut() {
+                    title.push_str(text);
+                }
+            }
+        }
+    }
+
+    titles
+}
+
+/// Reads the chapter titles of an EPUB 2 NCX table of contents, keyed by
+/// the document each one starts in.
+fn ncx_titles(ncx: &str, ncx_dir: &str) -> HashMap<String, String> {
+    let mut titles = HashMap::new();
+    let mut in_label = false;
+    let mut label = String::new();
+
+    for token in tokens(ncx) {
+        match token {
+            Token::StartTag {
+                name, attributes, ..
+            } => match local_name(name) {
+                "navLabel" => {
+                    in_label = true;
+                    label.clear();
+                }
+                "content" => {
+                    if let Some(src) = attribute(attributes, "src") {
+                        add_title(&mut titles, resolve_href(ncx_dir, &src), &label);
+                    }
+                }
+                _ => {}
+            },
+            Token::EndTag { name } if local_name(name) == "navLabel" => in_label = false,
+            Token::Text(text) if in_label => label.push_str(text),
+            _ => {}
+        }
+    }
+
+    titles
+}
+
+/// Keeps the first title found for each document, as later ones point
+/// to sections within it.
+fn add_title(titles: &mut HashMap<String, String>, document: String, title: &str) {
+    let title = decode_entities(title)
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ");
+
+    if !title.is_empty() {
+        titles.entry(document).or_insert(title);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
+
+    const CONTAINER: &str = r#"<?xml version="1.0"?>
+<container xmlns="urn:oasis:names:tc:opendocument:xmlns:container" version="1.0">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#;
+
+    const PACKAGE: &str = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="one" href="text/chapter%201.xhtml" media-type="application/xhtml+xml"/>
+    <item id="two" href="text/chapter2.xhtml" media-type="application/xhtml+xml"/>
+    <item id="cover" href="cover.jpg" media-type="image/jpeg"/>
+  </manifest>
+  <spine>
+    <itemref idref="two"/>
+    <itemref idref="nav" linear="no"/>
+    <itemref idref="one"/>
+  </spine>
+</package>"#;
+
+    const NAV: &str = r#"<html xmlns:epub="http://www.idpf.org/2007/ops"><body>
+  <nav epub:type="toc"><ol>
+    <li><a href="text/chapter2.xhtml">The   Beginning</a></li>
+    <li><a href="text/chapter%201.xhtml#start">The End</a></li>
+  </ol></nav>
+</body></html>"#;
+
+    fn create_epub(entries: &[(&str, &str)]) -> tempfile::NamedTempFile {
+        let epub_file = tempfile::NamedTempFile::new().unwrap();
+
+        let mut writer = zip::ZipWriter::new(epub_file.reopen().unwrap());
+        for (entry_name, contents) in entries {
+            writer
+                .start_file(*entry_name, FileOptions::default())
+                .unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        epub_file
+    }
+
+    #[test]
+    fn reads_spine_in_order_with_nav_titles() {
+        let epub_file = create_epub(&[
+            ("mimetype", "application/epub+zip"),
+            ("META-INF/container.xml", CONTAINER),
+            ("OEBPS/content.opf", PACKAGE),
+            ("OEBPS/nav.xhtml", NAV),
+            (
+                "OEBPS/text/chapter 1.xhtml",
+                "<html><body><h1>Two</h1><p>Last words.</p></body></html>",
+            ),
+            (
+                "OEBPS/text/chapter2.xhtml",
+                "<html><body><h1>One</h1><p>First words.</p></body></html>",
+            ),
+        ]);
+
+        let source_text = read_epub(epub_file.path(), &SourceOptions::default()).unwrap();
+
+        assert_eq!(
+            source_text.text,
+            "One\n\nFirst words.\n\nTwo\n\nLast words."
+        );
+        assert_eq!(
+            source_text.chapter_breaks,
+            [
+                ChapterBreak {
+                    offset: 0,
+                    title: String::from("The Beginning")
+                },
+                ChapterBreak {
+                    offset: 19,
+                    title: String::from("The End")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_ncx_titles() {
+        let ncx = r#"<ncx><navMap>
+  <navPoint id="p1"><navLabel><text>Prologue</text></navLabel><content src="Text/intro.html"/></navPoint>
+  <navPoint id="p2"><navLabel><text>Prologue, Part 2</text></navLabel><content src="Text/intro.html#p2"/></navPoint>
+</navMap></ncx>"#;
+
+        let titles = ncx_titles(ncx, "OEBPS/");
+        assert_eq!(titles.len(), 1);
+        assert_eq!(titles["OEBPS/Text/intro.html"], "Prologue");
+    }
+
+    #[test]
+    fn resolves_relative_links() {
+        assert_eq!(
+            resolve_href("OEBPS/text/", "../images/a%20b.png#x"),
+            "OEBPS/images/a b.png"
+        );
+        assert_eq!(resolve_href("", "./chapter.xhtml"), "chapter.xhtml");
+    }
+
+    #[test]
+    fn missing_container_fails() {
+        let epub_file = create_epub(&[("mimetype", "application/epub+zip")]);
+
+        assert!(read_epub(epub_file.path(), &SourceOptions::default()).is_err());
+    }
+}
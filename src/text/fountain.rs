@@ -0,0 +1,280 @@
+//! Screenplays written in Fountain, a plain text format for scripts.
+//! See https://fountain.io/syntax for how one is laid out.
+
+use regex::Regex;
+
+use crate::text::chapters::ChapterBreak;
+use crate::text::source::{Passage, SourceText};
+
+/// Scene headings start with where the scene takes place, like "INT. HOUSE - DAY".
+const SCENE_HEADING_PATTERN: &str = r"^(?i:int|ext|est|int\./ext|int/ext|i/e)[. ]";
+
+/// Scene numbers like "#12A#" closing a scene heading.
+const SCENE_NUMBER_PATTERN: &str = r"\s*#[^#]*#\s*$";
+
+/// Character extensions like "(V.O.)" or "(CONT'D)" following a name.
+const CHARACTER_EXTENSION_PATTERN: &str = r"\s*\([^)]*\)\s*";
+
+#[derive(PartialEq, Eq, Debug)]
+enum ScriptElement {
+    SceneHeading(String),
+    Action(String),
+    Dialogue { character: String, text: String },
+}
+
+/// Reads a Fountain screenplay as one passage for each scene heading,
+/// block of action and line of dialogue, with every scene starting a
+/// chapter. Dialogue passages are tagged with who speaks them.
+pub fn read_fountain(script: &str) -> SourceText {
+    let mut source_text = SourceText::default();
+
+    for element in script_elements(script) {
+        if !source_text.text.is_empty() {
+            source_text.text.push_str("\n\n");
+        }
+        let offset = source_text.text.len();
+
+        let (text, speaker) = match element {
+            ScriptElement::SceneHeading(heading) => {
+                source_text.chapter_breaks.push(ChapterBreak {
+                    offset,
+                    title: heading.clone(),
+                });
+                (heading, None)
+            }
+            ScriptElement::Action(text) => (text, None),
+            ScriptElement::Dialogue { character, text } => (text, Some(character)),
+        };
+
+        source_text.text.push_str(&text);
+        source_text.passages.push(Passage { offset, speaker });
+    }
+
+    source_text
+}
+
+/// Splits the script into the elements worth reading out, leaving out its
+/// title page, notes, comments, transitions, sections and synopses.
+fn script_elements(script: &str) -> Vec<ScriptElement> {
+    let scene_heading =
+        Regex::new(SCENE_HEADING_PATTERN).expect("Scene heading pattern should be valid.");
+    let scene_number =
+        Regex::new(SCENE_NUMBER_PATTERN).expect("Scene number pattern should be valid.");
+    let character_extension = Regex::new(CHARACTER_EXTENSION_PATTERN)
+        .expect("Character extension pattern should be valid.");
+
+    let script = script.replace("\r\n", "\n");
+    let script = remove_enclosed(&remove_enclosed(&script, "/*", "*/"), "[[", "]]");
+
+    let mut elements = Vec::new();
+    for (block_num, block) in script.split("\n\n").enumerate() {
+        let lines: Vec<&str> = block
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        let Some(&first_line) = lines.first() else {
+            continue;
+        };
+
+        if block_num == 0 && is_title_page(first_line) {
+            continue;
+        }
+
+        let is_forced_heading = first_line.starts_with('.') && !first_line.starts_with("..");
+        if is_forced_heading || scene_heading.is_match(first_line) {
+            let heading = first_line.strip_prefix('.').unwrap_or(first_line);
+            let heading = scene_number.replace(heading, "");
+            elements.push(ScriptElement::SceneHeading(strip_emphasis(heading.trim())));
+
+            if lines.len() > 1 {
+                elements.push(ScriptElement::Action(strip_emphasis(
+                    &lines[1..].join("\n"),
+                )));
+            }
+            continue;
+        }
+
+        if first_line.starts_with('#')
+            || first_line.starts_with('=')
+            || is_transition(first_line, &lines)
+        {
+            continue;
+        }
+
+        if lines.len() > 1 && is_character(first_line, &character_extension) {
+            let character = first_line.trim_start_matches('@').trim_end_matches('^');
+            let character = character_extension.replace_all(character, " ");
+
+            elements.push(ScriptElement::Dialogue {
+                character: character.trim().to_string(),
+                text: strip_emphasis(&lines[1..].join("\n")),
+            });
+            continue;
+        }
+
+        let action = lines
+            .iter()
+            .map(|line| {
+                let line = line.trim_start_matches('!');
+                let line = line.trim_start_matches('~');
+                // Centered text, like "> THE END <"
+                match line
+                    .strip_prefix('>')
+                    .and_then(|line| line.strip_suffix('<'))
+                {
+                    Some(centered) => centered.trim(),
+                    None => line,
+                }
+            })
+            .collect::<Vec<&str>>()
+            .join("\n");
+        elements.push(ScriptElement::Action(strip_emphasis(&action)));
+    }
+
+    elements
+}
+
+/// Title pages start with a key and value, like "Title: Big Fish".
+fn is_title_page(first_line: &str) -> bool {
+    first_line.split_once(':').is_some_and(|(key, _)| {
+        !key.is_empty()
+            && key
+                .chars()
+                .all(|character| character.is_alphabetic() || character == ' ')
+    })
+}
+
+fn is_transition(first_line: &str, lines: &[&str]) -> bool {
+    if first_line.starts_with('>') {
+        return !first_line.ends_with('<');
+    }
+
+    lines.len() == 1 && first_line.ends_with("TO:") && !first_line.chars().any(char::is_lowercase)
+}
+
+/// Character names are on a line of their own in capitals, unless forced with an "@".
+fn is_character(first_line: &str, character_extension: &Regex) -> bool {
+    if first_line.starts_with('@') {
+        return true;
+    }
+    if first_line.starts_with('!') {
+        return false;
+    }
+
+    let name = character_extension.replace_all(first_line, "");
+    name.chars().any(char::is_alphabetic) && !name.chars().any(char::is_lowercase)
+}
+
+/// Takes out everything between the start and end markers, the markers included.
+fn remove_enclosed(text: &str, start_marker: &str, end_marker: &str) -> String {
+    let mut remaining = text;
+    let mut kept = String::with_capacity(text.len());
+
+    while let Some(start) = remaining.find(start_marker) {
+        kept.push_str(&remaining[..start]);
+
+        let after_start = &remaining[start + start_marker.len()..];
+        remaining = after_start
+            .find(end_marker)
+            .map_or("", |end| &after_start[end + end_marker.len()..]);
+    }
+
+    kept.push_str(remaining);
+    kept
+}
+
+/// Drops the asterisks and underscores marking bold, italic and underlined
+/// text, keeping the ones escaped with a backslash.
+fn strip_emphasis(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+
+    let mut characters = text.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => stripped.extend(characters.next()),
+            '*' | '_' => {}
+            character => stripped.push(character),
+        }
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = "Title: The Storm
+Author: Someone
+
+INT. LIGHTHOUSE - NIGHT #1#
+
+Rain lashes the *windows*. [[Check the sound effects.]]
+
+MARY (V.O.)
+(whispering)
+Is anyone there?
+
+/* Cut this bit
+TOM
+Nobody.
+*/
+
+CUT TO:
+
+.THE CLIFFS
+
+@McCLANE
+Yippee ki-yay.
+
+!WIND howls.
+";
+
+    #[test]
+    fn splits_script_into_elements() {
+        assert_eq!(
+            script_elements(SCRIPT),
+            [
+                ScriptElement::SceneHeading(String::from("INT. LIGHTHOUSE - NIGHT")),
+                ScriptElement::Action(String::from("Rain lashes the windows.")),
+                ScriptElement::Dialogue {
+                    character: String::from("MARY"),
+                    text: String::from("(whispering)\nIs anyone there?")
+                },
+                ScriptElement::SceneHeading(String::from("THE CLIFFS")),
+                ScriptElement::Dialogue {
+                    character: String::from("McCLANE"),
+                    text: String::from("Yippee ki-yay.")
+                },
+                ScriptElement::Action(String::from("WIND howls.")),
+            ]
+        );
+    }
+
+    #[test]
+    fn scenes_are_chapters_and_lines_are_passages() {
+        let source_text = read_fountain(SCRIPT);
+
+        assert_eq!(
+            source_text.chapter_breaks,
+            [
+                ChapterBreak {
+                    offset: 0,
+                    title: String::from("INT. LIGHTHOUSE - NIGHT")
+                },
+                ChapterBreak {
+                    offset: 82,
+                    title: String::from("THE CLIFFS")
+                },
+            ]
+        );
+
+        assert_eq!(source_text.passages.len(), 6);
+        assert_eq!(source_text.passages[2].speaker.as_deref(), Some("MARY"));
+        assert_eq!(
+            &source_text.text[source_text.passages[2].offset..source_text.passages[3].offset],
+            "(whispering)\nIs anyone there?\n\n"
+        );
+    }
+}
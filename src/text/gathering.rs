@@ -0,0 +1,311 @@
+use anyhow::{bail, Result};
+use regex::Regex;
+
+use crate::text::sentences::SentenceSplitter;
+
+/// How the text gets gathered into paragraphs to be read one at a time.
+#[derive(Clone, Debug)]
+pub enum Gathering {
+    /// An amount of sentences at a time, each ending with any of the delimiters.
+    Delimited { delimiters: String, amount: usize },
+    /// An amount of sentences at a time, found by their Unicode boundaries.
+    Sentences {
+        splitter: SentenceSplitter,
+        amount: usize,
+    },
+    /// The text's own paragraphs, set apart by blank lines. Ones longer
+    /// than the maximum length in characters are split between sentences.
+    Paragraphs {
+        splitter: SentenceSplitter,
+        max_length: Option<usize>,
+    },
+    /// As many whole sentences at a time as it takes to reach the target
+    /// duration, going by how many words are read in a minute.
+    Duration {
+        splitter: SentenceSplitter,
+        words_per_minute: usize,
+        target_secs: usize,
+    },
+    /// An amount of pieces at a time, each ending with a match of the
+    /// boundary pattern, once any lines matching the skipped lines
+    /// pattern are taken out.
+    Pattern {
+        boundary: Regex,
+        skipped_lines: Option<Regex>,
+        amount: usize,
+    },
+}
+
+impl Gathering {
+    /// Gathers by regular expressions, with an empty skipped lines pattern
+    /// keeping every line.
+    pub fn pattern(
+        boundary_pattern: &str,
+        skipped_lines_pattern: &str,
+        amount: usize,
+    ) -> Result<Gathering> {
+        if boundary_pattern.is_empty() {
+            bail!("The boundary regex is empty.");
+        }
+
+        let skipped_lines = match skipped_lines_pattern {
+            "" => None,
+            pattern => Some(Regex::new(pattern)?),
+        };
+
+        Ok(Gathering::Pattern {
+            boundary: Regex::new(boundary_pattern)?,
+            skipped_lines,
+            amount,
+        })
+    }
+
+    /// Gathers the text into paragraphs, which together make up all of it
+    /// unless some lines are skipped when gathering by regex.
+    pub fn paragraphs(&self, text: &str) -> Vec<String> {
+        match self {
+            Gathering::Delimited { delimiters, amount } => {
+                let delimiter_tokens = delimiters.chars().collect::<Vec<char>>();
+                let sentences = text.split_inclusive(&*delimiter_tokens).collect();
+
+                gather_sentences(sentences, *amount)
+            }
+            Gathering::Sentences { splitter, amount } => {
+                gather_sentences(splitter.split(text), *amount)
+            }
+            Gathering::Paragraphs {
+                splitter,
+                max_length,
+            } => source_paragraphs(text)
+                .into_iter()
+                .flat_map(|paragraph| match max_length {
+                    Some(max_length) if paragraph.chars().count() > *max_length => {
+                        shorten_paragraph(splitter.split(paragraph), *max_length)
+                    }
+                    _ => vec![paragraph.to_string()],
+                })
+                .collect(),
+            Gathering::Duration {
+                splitter,
+                words_per_minute,
+                target_secs,
+            } => gather_by_duration(splitter.split(text), *words_per_minute, *target_secs),
+            Gathering::Pattern {
+                boundary,
+                skipped_lines,
+                amount,
+            } => {
+                let kept_text = match skipped_lines {
+                    Some(skipped_lines) => remove_lines(text, skipped_lines),
+                    None => text.to_string(),
+                };
+
+                gather_sentences(split_after_matches(&kept_text, boundary), *amount)
+            }
+        }
+    }
+}
+
+/// Estimates how many seconds reading the text aloud takes.
+pub fn estimate_secs(text: &str, words_per_minute: usize) -> f64 {
+    let num_words = text.split_whitespace().count();
+
+    num_words as f64 * 60.0 / words_per_minute.max(1) as f64
+}
+
+/// Puts the sentences together an amount at a time.
+fn gather_sentences(sentences: Vec<&str>, amount: usize) -> Vec<String> {
+    let mut sentences: Vec<String> = sentences.into_iter().map(String::from).collect();
+
+    // Leftover whitespace belongs with the sentence before it, rather
+    // than being a paragraph of its own at the end of every chapter.
+    if sentences.len() > 1 && sentences[sentences.len() - 1].trim().is_empty() {
+        let trailing_whitespace = sentences.pop().unwrap();
+        sentences.last_mut().unwrap().push_str(&trailing_whitespace);
+    }
+
+    sentences
+        .chunks(amount)
+        .map(|sentences| sentences.concat())
+        .collect()
+}
+
+/// Puts sentences together until they reach the target duration, with
+/// the last paragraph being whatever is left.
+fn gather_by_duration(
+    sentences: Vec<&str>,
+    words_per_minute: usize,
+    target_secs: usize,
+) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut paragraph = String::new();
+
+    for sentence in sentences {
+        paragraph.push_str(sentence);
+
+        if estimate_secs(&paragraph, words_per_minute) >= target_secs as f64 {
+            paragraphs.push(std::mem::take(&mut paragraph));
+        }
+    }
+
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+
+    paragraphs
+}
+
+/// Takes out every line matching the pattern, along with its line break.
+fn remove_lines(text: &str, pattern: &Regex) -> String {
+    text.split_inclusive('\n')
+        .filter(|line| !pattern.is_match(line.trim_end_matches(['\r', '\n'])))
+        .collect()
+}
+
+/// Splits the text after every match of the pattern, keeping the matches.
+fn split_after_matches<'a>(text: &'a str, pattern: &Regex) -> Vec<&'a str> {
+    let mut pieces = Vec::new();
+    let mut piece_start = 0;
+
+    for found in pattern.find_iter(text) {
+        // Empty matches, like those of "$", can't end an empty piece
+        if found.end() > piece_start {
+            pieces.push(&text[piece_start..found.end()]);
+            piece_start = found.end();
+        }
+    }
+
+    if piece_start < text.len() {
+        pieces.push(&text[piece_start..]);
+    }
+
+    pieces
+}
+
+/// Splits the text after every run of blank lines, which stay with the
+/// paragraph before them.
+fn source_paragraphs(text: &str) -> Vec<&str> {
+    let mut paragraphs = Vec::new();
+    let mut paragraph_start = 0;
+    let mut line_start = 0;
+    let mut has_content = false;
+    let mut after_blank_line = false;
+
+    for line in text.split_inclusive('\n') {
+        let is_blank = line.trim().is_empty();
+
+        if !is_blank && has_content && after_blank_line {
+            paragraphs.push(&text[paragraph_start..line_start]);
+            paragraph_start = line_start;
+        }
+
+        has_content |= !is_blank;
+        after_blank_line = is_blank && has_content;
+        line_start += line.len();
+    }
+
+    if paragraph_start < text.len() {
+        paragraphs.push(&text[paragraph_start..]);
+    }
+
+    paragraphs
+}
+
+/// Gathers as many of a paragraph's sentences at a time as fit within the
+/// maximum length, with a sentence too long by itself kept whole.
+fn shorten_paragraph(sentences: Vec<&str>, max_length: usize) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut paragraph = String::new();
+
+    for sentence in sentences {
+        if !paragraph.is_empty()
+            && paragraph.chars().count() + sentence.chars().count() > max_length
+        {
+            paragraphs.push(std::mem::take(&mut paragraph));
+        }
+        paragraph.push_str(sentence);
+    }
+
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph);
+    }
+
+    paragraphs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paragraphs_follow_blank_lines() {
+        let text = "\nFirst line,\nsame paragraph.\n\n  \n\nSecond. Still second.\n\nThird.";
+        let gathering = Gathering::Paragraphs {
+            splitter: SentenceSplitter::default(),
+            max_length: None,
+        };
+
+        assert_eq!(
+            gathering.paragraphs(text),
+            [
+                "\nFirst line,\nsame paragraph.\n\n  \n\n",
+                "Second. Still second.\n\n",
+                "Third."
+            ]
+        );
+    }
+
+    #[test]
+    fn long_paragraphs_split_between_sentences() {
+        let text = "One two. Three four. Five six seven eight nine.\n\nShort.";
+        let gathering = Gathering::Paragraphs {
+            splitter: SentenceSplitter::default(),
+            max_length: Some(22),
+        };
+
+        assert_eq!(
+            gathering.paragraphs(text),
+            [
+                "One two. Three four. ",
+                "Five six seven eight nine.\n\n",
+                "Short."
+            ]
+        );
+    }
+
+    #[test]
+    fn sentences_gather_until_target_duration() {
+        // At 60 words a minute, every word takes a second
+        let text = "One two three. Four five. Six. Seven eight nine ten eleven. Twelve.";
+        let gathering = Gathering::Duration {
+            splitter: SentenceSplitter::default(),
+            words_per_minute: 60,
+            target_secs: 5,
+        };
+
+        assert_eq!(
+            gathering.paragraphs(text),
+            [
+                "One two three. Four five. ",
+                "Six. Seven eight nine ten eleven. ",
+                "Twelve."
+            ]
+        );
+        assert_eq!(estimate_secs("One two three.", 120), 1.5);
+    }
+
+    #[test]
+    fn patterns_split_and_skip_lines() {
+        let text = "Once upon a time;\n12\nthere was a king.\n* * *\nThe end";
+        let gathering = Gathering::pattern(r"[;.]\s*", r"^(\d+|\* \* \*)$", 1).unwrap();
+
+        assert_eq!(
+            gathering.paragraphs(text),
+            ["Once upon a time;\n", "there was a king.\n", "The end"]
+        );
+
+        assert!(Gathering::pattern("", "", 1).is_err());
+        assert!(Gathering::pattern("[", "", 1).is_err());
+        assert!(Gathering::pattern(r"\.", "(", 1).is_err());
+    }
+}
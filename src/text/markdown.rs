@@ -0,0 +1,163 @@
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+use crate::text::chapters::ChapterBreak;
+use crate::text::markup::{end_paragraph, html_to_text};
+use crate::text::source::SourceText;
+
+/// Renders Markdown as the plain text it reads as, with a blank line
+/// between blocks and a chapter starting at every heading. Images, link
+/// targets and formatting are left out, as are code blocks unless
+/// they're to be read.
+pub fn markdown_to_text(markdown: &str, read_code_blocks: bool) -> SourceText {
+    let mut source_text = SourceText::default();
+    let mut skipped_depth = 0usize;
+    let mut current_heading: Option<(usize, String)> = None;
+    let mut html_block = String::new();
+
+    let parser = Parser::new_ext(
+        markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_FOOTNOTES,
+    );
+
+    for event in parser {
+        match event {
+            Event::Start(Tag::Heading { .. }) => {
+                end_paragraph(&mut source_text.text);
+                current_heading = Some((source_text.text.len(), String::new()));
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some((offset, title)) = current_heading.take() {
+                    let title = title.split_whitespace().collect::<Vec<&str>>().join(" ");
+                    if !title.is_empty() {
+                        source_text
+                            .chapter_breaks
+                            .push(ChapterBreak { offset, title });
+                    }
+                }
+                end_paragraph(&mut source_text.text);
+            }
+            Event::Start(Tag::CodeBlock(_)) => {
+                end_paragraph(&mut source_text.text);
+                if !read_code_blocks {
+                    skipped_depth += 1;
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => {
+                if !read_code_blocks {
+                    skipped_depth = skipped_depth.saturating_sub(1);
+                }
+                end_paragraph(&mut source_text.text);
+            }
+            Event::Start(Tag::Image { .. } | Tag::MetadataBlock(_)) => skipped_depth += 1,
+            Event::End(TagEnd::Image | TagEnd::MetadataBlock(_)) => {
+                skipped_depth = skipped_depth.saturating_sub(1);
+            }
+            Event::Start(
+                Tag::Paragraph
+                | Tag::BlockQuote(_)
+                | Tag::Item
+                | Tag::TableRow
+                | Tag::TableHead
+                | Tag::FootnoteDefinition(_),
+            )
+            | Event::End(
+                TagEnd::Paragraph
+                | TagEnd::BlockQuote(_)
+                | TagEnd::Item
+                | TagEnd::TableRow
+                | TagEnd::TableHead
+                | TagEnd::FootnoteDefinition,
+            )
+            | Event::Rule => end_paragraph(&mut source_text.text),
+            // Cells of a row read out as one line
+            Event::Start(Tag::TableCell)
+                if !source_text.text.is_empty()
+                    && !source_text.text.ends_with(char::is_whitespace) =>
+            {
+                source_text.text.push(' ');
+            }
+            Event::Html(html) => html_block.push_str(&html),
+            Event::End(TagEnd::HtmlBlock) => {
+                let block_text = html_to_text(&html_block, read_code_blocks).text;
+                if !block_text.is_empty() {
+                    end_paragraph(&mut source_text.text);
+                    source_text.text.push_str(&block_text);
+                    end_paragraph(&mut source_text.text);
+                }
+                html_block.clear();
+            }
+            Event::Text(text) | Event::Code(text) if skipped_depth == 0 => {
+                source_text.text.push_str(&text);
+                if let Some((_, title)) = current_heading.as_mut() {
+                    title.push_str(&text);
+                }
+            }
+            Event::SoftBreak if skipped_depth == 0 => source_text.text.push(' '),
+            Event::HardBreak if skipped_depth == 0 => source_text.text.push('\n'),
+            _ => {}
+        }
+    }
+
+    source_text.text.truncate(source_text.text.trim_end().len());
+    let text_len = source_text.text.len();
+    source_text
+        .chapter_breaks
+        .retain(|chapter_break| chapter_break.offset < text_len);
+
+    source_text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARKDOWN: &str = "# Chapter *One*
+
+It was a [dark](https://example.com) and **stormy**
+night. ![A storm](storm.png)
+
+```
+let rain = true;
+```
+
+- First
+- Second
+
+## Chapter Two
+
+<p>Some <b>HTML</b>.</p>
+";
+
+    #[test]
+    fn markdown_becomes_plain_text() {
+        let source_text = markdown_to_text(MARKDOWN, false);
+
+        assert_eq!(
+            source_text.text,
+            "Chapter One\n\nIt was a dark and stormy night.\n\nFirst\n\nSecond\n\n\
+            Chapter Two\n\nSome HTML."
+        );
+        assert_eq!(
+            source_text.chapter_breaks,
+            [
+                ChapterBreak {
+                    offset: 0,
+                    title: String::from("Chapter One")
+                },
+                ChapterBreak {
+                    offset: 61,
+                    title: String::from("Chapter Two")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn code_blocks_are_optional() {
+        let source_text = markdown_to_text(MARKDOWN, true);
+
+        assert!(source_text
+            .text
+            .contains("stormy night.\n\nlet rain = true;\n\nFirst"));
+    }
+}
@@ -0,0 +1,442 @@
+//! Just enough of an HTML/XML reader to pull the text out of the
+//! documents books come in, without caring whether they're well formed.
+
+use crate::text::chapters::ChapterBreak;
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum Token<'a> {
+    StartTag {
+        name: &'a str,
+        attributes: &'a str,
+        self_closing: bool,
+    },
+    EndTag {
+        name: &'a str,
+    },
+    Text(&'a str),
+}
+
+/// Splits markup into tags and the text between them. Comments,
+/// declarations and processing instructions are skipped, while the
+/// contents of CDATA sections are treated as text.
+pub fn tokens(markup: &str) -> impl Iterator<Item = Token<'_>> {
+    let mut remaining = markup;
+
+    std::iter::from_fn(move || loop {
+        if remaining.is_empty() {
+            return None;
+        }
+
+        let Some(tag_start) = remaining.find('<') else {
+            let text = remaining;
+            remaining = "";
+            return Some(Token::Text(text));
+        };
+
+        if tag_start > 0 {
+            let text = &remaining[..tag_start];
+            remaining = &remaining[tag_start..];
+            return Some(Token::Text(text));
+        }
+
+        if let Some(after_comment) = remaining.strip_prefix("<!--") {
+            remaining = after_comment
+                .find("-->")
+                .map_or("", |comment_end| &after_comment[comment_end + 3..]);
+            continue;
+        }
+
+        if let Some(after_cdata) = remaining.strip_prefix("<![CDATA[") {
+            let cdata_end = after_cdata.find("]]>").unwrap_or(after_cdata.len());
+            let text = &after_cdata[..cdata_end];
+            remaining = after_cdata.get(cdata_end + 3..).unwrap_or("");
+            return Some(Token::Text(text));
+        }
+
+        let Some(tag_end) = remaining.find('>') else {
+            remaining = "";
+            return None;
+        };
+
+        let tag = &remaining[1..tag_end];
+        remaining = &remaining[tag_end + 1..];
+
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+
+        if let Some(name) = tag.strip_prefix('/') {
+            return Some(Token::EndTag { name: name.trim() });
+        }
+
+        let self_closing = tag.ends_with('/');
+        let tag = tag.trim_end_matches('/');
+        let name_end = tag
+            .find(|character: char| character.is_whitespace())
+            .unwrap_or(tag.len());
+
+        return Some(Token::StartTag {
+            name: &tag[..name_end],
+            attributes: &tag[name_end..],
+            self_closing,
+        });
+    })
+}
+
+/// Returns the name without any namespace prefix, such as "type" from "epub:type".
+pub fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Finds the value of an attribute in the attributes of a start tag.
+pub fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut remaining = attributes;
+
+    while let Some(equals) = remaining.find('=') {
+        let attribute_name = remaining[..equals].split_whitespace().last().unwrap_or("");
+        let value_part = remaining[equals + 1..].trim_start();
+
+        let (value, rest) = match value_part.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let value_end = value_part[1..]
+                    .find(quote)
+                    .map_or(value_part.len(), |end| end + 1);
+                (
+                    &value_part[1..value_end],
+                    value_part.get(value_end + 1..).unwrap_or(""),
+                )
+            }
+            _ => {
+                let value_end = value_part
+                    .find(|character: char| character.is_whitespace())
+                    .unwrap_or(value_part.len());
+                (&value_part[..value_end], &value_part[value_end..])
+            }
+        };
+
+        if attribute_name == name {
+            return Some(decode_entities(value));
+        }
+
+        remaining = rest;
+    }
+
+    None
+}
+
+/// Replaces character references like "&amp;" or "&#8217;" with the characters they stand for.
+pub fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut remaining = text;
+
+    while let Some(ampersand) = remaining.find('&') {
+        decoded.push_str(&remaining[..ampersand]);
+        remaining = &remaining[ampersand..];
+
+        let entity = remaining[1..]
+            .find(';')
+            .filter(|&entity_end| entity_end <= 10)
+            .and_then(|entity_end| {
+                let name = &remaining[1..entity_end + 1];
+                entity_character(name).map(|character| (character, entity_end + 2))
+            });
+
+        match entity {
+            Some((character, entity_len)) => {
+                decoded.push(character);
+                remaining = &remaining[entity_len..];
+            }
+            None => {
+                decoded.push('&');
+                remaining = &remaining[1..];
+            }
+        }
+    }
+
+    decoded.push_str(remaining);
+    decoded
+}
+
+fn entity_character(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code_point = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+
+        return char::from_u32(code_point);
+    }
+
+    let character = match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "copy" => '©',
+        _ => return None,
+    };
+
+    Some(character)
+}
+
+/// Elements whose contents are never read out.
+const HIDDEN_ELEMENTS: [&str; 6] = ["head", "script", "style", "title", "svg", "math"];
+
+/// Elements that separate the text around them into paragraphs.
+const BLOCK_ELEMENTS: [&str; 24] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "footer",
+    "header",
+    "hr",
+    "li",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+fn is_heading(name: &str) -> bool {
+    matches!(name, "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+}
+
+/// The readable text of an HTML document, with a blank line between
+/// paragraphs, along with where each of its headings begin.
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct HtmlText {
+    pub text: String,
+    pub headings: Vec<ChapterBreak>,
+}
+
+/// Strips the markup from an HTML or XHTML document, keeping only the
+/// text a reader would see. Images, link targets and anything in the
+/// document's head are left out, as are preformatted blocks of code
+/// unless they're to be read.
+pub fn html_to_text(html: &str, read_code_blocks: bool) -> HtmlText {
+    let mut html_text = HtmlText::default();
+    let mut hidden_depth = 0usize;
+    let mut current_heading: Option<(usize, String)> = None;
+    let mut pending_space = false;
+
+    for token in tokens(html) {
+        match token {
+            Token::StartTag {
+                name, self_closing, ..
+            } => {
+                let name = local_name(name).to_ascii_lowercase();
+
+                if HIDDEN_ELEMENTS.contains(&name.as_str()) || (name == "pre" && !read_code_blocks)
+                {
+                    if !self_closing {
+                        hidden_depth += 1;
+                    }
+                } else if is_heading(&name) {
+                    end_paragraph(&mut html_text.text);
+                    current_heading = Some((html_text.text.len(), String::new()));
+                    pending_space = false;
+                } else if name == "br" {
+                    end_line(&mut html_text.text);
+                    pending_space = false;
+                } else if BLOCK_ELEMENTS.contains(&name.as_str()) {
+                    end_paragraph(&mut html_text.text);
+                    pending_space = false;
+                }
+            }
+            Token::EndTag { name } => {
+                let name = local_name(name).to_ascii_lowercase();
+
+                if HIDDEN_ELEMENTS.contains(&name.as_str()) || (name == "pre" && !read_code_blocks)
+                {
+                    hidden_depth = hidden_depth.saturating_sub(1);
+                } else if is_heading(&name) {
+                    if let Some((offset, title)) = current_heading.take() {
+                        let title = title.split_whitespace().collect::<Vec<&str>>().join(" ");
+                        if !title.is_empty() {
+                            html_text.headings.push(ChapterBreak { offset, title });
+                        }
+                    }
+                    end_paragraph(&mut html_text.text);
+                    pending_space = false;
+                } else if BLOCK_ELEMENTS.contains(&name.as_str()) {
+                    end_paragraph(&mut html_text.text);
+                    pending_space = false;
+                }
+            }
+            Token::Text(text) if hidden_depth == 0 => {
+                let text = decode_entities(text);
+
+                let mut words = text.split_whitespace().peekable();
+                if words.peek().is_none() {
+                    pending_space |= !text.is_empty();
+                } else {
+                    pending_space |= text.starts_with(char::is_whitespace);
+
+                    for word in words {
+                        let at_paragraph_start =
+                            html_text.text.is_empty() || html_text.text.ends_with('\n');
+                        if pending_space && !at_paragraph_start {
+                            html_text.text.push(' ');
+                        }
+
+                        html_text.text.push_str(word);
+                        pending_space = true;
+                    }
+
+                    pending_space = text.ends_with(char::is_whitespace);
+                }
+
+                if let Some((_, title)) = current_heading.as_mut() {
+                    title.push_str(&text);
+                }
+            }
+            Token::Text(_) => {}
+        }
+    }
+
+    end_paragraph(&mut html_text.text);
+    html_text.text.truncate(html_text.text.trim_end().len());
+    html_text
+        .headings
+        .retain(|heading| heading.offset < html_text.text.len());
+
+    html_text
+}
+
+/// Separates what comes next from the text so far with a blank line.
+pub fn end_paragraph(text: &mut String) {
+    if text.is_empty() || text.ends_with("\n\n") {
+        return;
+    }
+
+    text.truncate(text.trim_end_matches([' ', '\n']).len());
+    text.push_str("\n\n");
+}
+
+/// Starts a new line within the paragraph, like between the lines of a
+/// verse or an address.
+fn end_line(text: &mut String) {
+    if text.is_empty() || text.ends_with("\n\n") {
+        return;
+    }
+
+    text.truncate(text.trim_end_matches(' ').len());
+    text.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizes_tags_and_text() {
+        let tokens: Vec<Token> = tokens("<?xml?><!-- note --><p class=\"a\">Hi<br/></p>").collect();
+
+        assert_eq!(
+            tokens,
+            [
+                Token::StartTag {
+                    name: "p",
+                    attributes: " class=\"a\"",
+                    self_closing: false
+                },
+                Token::Text("Hi"),
+                Token::StartTag {
+                    name: "br",
+                    attributes: "",
+                    self_closing: true
+                },
+                Token::EndTag { name: "p" },
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_attributes() {
+        let attributes = " id='intro' href=\"ch1.xhtml#top\" data-x=plain title=\"A &amp; B\"";
+
+        assert_eq!(attribute(attributes, "id").as_deref(), Some("intro"));
+        assert_eq!(
+            attribute(attributes, "href").as_deref(),
+            Some("ch1.xhtml#top")
+        );
+        assert_eq!(attribute(attributes, "data-x").as_deref(), Some("plain"));
+        assert_eq!(attribute(attributes, "title").as_deref(), Some("A & B"));
+        assert_eq!(attribute(attributes, "missing"), None);
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!(
+            decode_entities("Tom &amp; Jerry&#8217;s &#x201C;show&rdquo; &unknown; & more"),
+            "Tom & Jerry’s “show” &unknown; & more"
+        );
+    }
+
+    #[test]
+    fn html_becomes_paragraphs() {
+        let html = "<html><head><title>Skip</title><style>p {}</style></head><body>
+            <h1>Chapter  One</h1>
+            <p>It was <em>very</em>
+               dark. <a href=\"https://example.com\">Really</a>.</p>
+            <p>Next<br/>line. <img src=\"x.png\" alt=\"picture\"/></p>
+            </body></html>";
+
+        let html_text = html_to_text(html, true);
+
+        assert_eq!(
+            html_text.text,
+            "Chapter One\n\nIt was very dark. Really.\n\nNext\nline."
+        );
+        assert_eq!(
+            html_text.headings,
+            [ChapterBreak {
+                offset: 0,
+                title: String::from("Chapter One")
+            }]
+        );
+    }
+
+    #[test]
+    fn line_breaks_stay_in_paragraph() {
+        let html = "<p>Roses are red,<br>violets are blue.</p><p>Two<br/><br/>apart.</p>";
+
+        assert_eq!(
+            html_to_text(html, true).text,
+            "Roses are red,\nviolets are blue.\n\nTwo\n\napart."
+        );
+    }
+
+    #[test]
+    fn code_blocks_are_optional() {
+        let html = "<p>Run this:</p><pre><code>cargo run</code></pre><p>Done.</p>";
+
+        assert_eq!(
+            html_to_text(html, true).text,
+            "Run this:\n\ncargo run\n\nDone."
+        );
+        assert_eq!(html_to_text(html, false).text, "Run this:\n\nDone.");
+    }
+}
@@ -1 +1,14 @@
+pub mod chapters;
+pub mod encoding;
+pub mod epub;
+pub mod fountain;
+pub mod gathering;
+pub mod markdown;
+pub mod markup;
+pub mod office;
+pub mod paragraph_ids;
+pub mod realignment;
+pub mod search;
+pub mod sentences;
+pub mod source;
 pub mod viewer;
@@ -0,0 +1,192 @@
+//! Body text of word processor documents, which are zipped XML.
+
+use std::fs::File;
+use std::path::Path;
+
+use anyhow::Result;
+use zip::ZipArchive;
+
+use crate::text::markup::{attribute, decode_entities, local_name, tokens, Token};
+use crate::text::source::{read_archive_entry, SourceText};
+
+/// Reads the body text of a Word document, one paragraph after another.
+pub fn read_docx(docx_file: &Path) -> Result<SourceText> {
+    let mut archive = ZipArchive::new(File::open(docx_file)?)?;
+    let document = read_archive_entry(&mut archive, "word/document.xml")?;
+
+    Ok(SourceText {
+        text: docx_text(&document),
+        chapter_breaks: Vec::new(),
+        passages: Vec::new(),
+    })
+}
+
+/// Reads the body text of an OpenDocument text document, one paragraph
+/// after another.
+pub fn read_odt(odt_file: &Path) -> Result<SourceText> {
+    let mut archive = ZipArchive::new(File::open(odt_file)?)?;
+    let content = read_archive_entry(&mut archive, "content.xml")?;
+
+    Ok(SourceText {
+        text: odt_text(&content),
+        chapter_breaks: Vec::new(),
+        passages: Vec::new(),
+    })
+}
+
+/// Adds a finished paragraph to the text, with a blank line before it.
+fn push_paragraph(text: &mut String, paragraph: &mut String) {
+    let finished_paragraph = paragraph.trim();
+
+    if !finished_paragraph.is_empty() {
+        if !text.is_empty() {
+            text.push_str("\n\n");
+        }
+        text.push_str(finished_paragraph);
+    }
+
+    paragraph.clear();
+}
+
+/// Pulls the text out of a DOCX's document.xml. Only what's in its text
+/// runs is kept, leaving out deleted revisions and field codes.
+fn docx_text(document: &str) -> String {
+    let mut text = String::new();
+    let mut paragraph = String::new();
+    let mut in_text_run = false;
+
+    for token in tokens(document) {
+        match token {
+            Token::StartTag {
+                name, self_closing, ..
+            } => match local_name(name) {
+                // Paragraphs in text boxes sit inside another paragraph
+                "p" => push_paragraph(&mut text, &mut paragraph),
+                "t" => in_text_run = !self_closing,
+                "tab" => paragraph.push('\t'),
+                "br" | "cr" => paragraph.push('\n'),
+                _ => {}
+            },
+            Token::EndTag { name } => match local_name(name) {
+                "p" => push_paragraph(&mut text, &mut paragraph),
+                "t" => in_text_run = false,
+                _ => {}
+            },
+            Token::Text(run_text) if in_text_run => paragraph.push_str(&decode_entities(run_text)),
+            Token::Text(_) => {}
+        }
+    }
+
+    push_paragraph(&mut text, &mut paragraph);
+    text
+}
+
+/// Elements in an ODT whose text isn't part of the body's flow.
+const ODT_HIDDEN_ELEMENTS: [&str; 3] = ["note", "annotation", "tracked-changes"];
+
+/// Pulls the text out of an ODT's content.xml, from its paragraphs and
+/// headings. Footnotes, comments and tracked changes are left out.
+fn odt_text(content: &str) -> String {
+    let mut text = String::new();
+    let mut paragraph = String::new();
+    let mut paragraph_depth = 0usize;
+    let mut hidden_depth = 0usize;
+
+    for token in tokens(content) {
+        match token {
+            Token::StartTag {
+                name,
+                attributes,
+                self_closing,
+            } => match local_name(name) {
+                "p" | "h" if !self_closing && hidden_depth == 0 => {
+                    push_paragraph(&mut text, &mut paragraph);
+                    paragraph_depth += 1;
+                }
+                "s" if hidden_depth == 0 => {
+                    let num_spaces = attribute(attributes, "text:c")
+                        .and_then(|count| count.parse().ok())
+                        .unwrap_or(1);
+                    paragraph.push_str(&" ".repeat(num_spaces));
+                }
+                "tab" if hidden_depth == 0 => paragraph.push('\t'),
+                "line-break" if hidden_depth == 0 => paragraph.push('\n'),
+                name if ODT_HIDDEN_ELEMENTS.contains(&name) && !self_closing => hidden_depth += 1,
+                _ => {}
+            },
+            Token::EndTag { name } => match local_name(name) {
+                "p" | "h" if hidden_depth == 0 => {
+                    push_paragraph(&mut text, &mut paragraph);
+                    paragraph_depth = paragraph_depth.saturating_sub(1);
+                }
+                name if ODT_HIDDEN_ELEMENTS.contains(&name) => {
+                    hidden_depth = hidden_depth.saturating_sub(1);
+                }
+                _ => {}
+            },
+            Token::Text(paragraph_text) if paragraph_depth > 0 && hidden_depth == 0 => {
+                paragraph.push_str(&decode_entities(paragraph_text));
+            }
+            Token::Text(_) => {}
+        }
+    }
+
+    push_paragraph(&mut text, &mut paragraph);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::FileOptions;
+
+    #[test]
+    fn reads_docx_paragraphs() {
+        let document = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+  <w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Chapter One</w:t></w:r></w:p>
+  <w:p><w:r><w:t xml:space="preserve">It was </w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>dark</w:t></w:r><w:del><w:r><w:delText>cold</w:delText></w:r></w:del><w:r><w:t>. Tom &amp; Ann</w:t><w:br/><w:t>waited.</w:t></w:r></w:p>
+  <w:p/>
+  <w:p><w:r><w:fldChar w:fldCharType="begin"/><w:instrText>PAGE</w:instrText><w:t>The end.</w:t></w:r></w:p>
+</w:body></w:document>"#;
+
+        assert_eq!(
+            docx_text(document),
+            "Chapter One\n\nIt was dark. Tom & Ann\nwaited.\n\nThe end."
+        );
+    }
+
+    #[test]
+    fn reads_odt_paragraphs() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0">
+  <office:automatic-styles><style:style style:name="P1"/></office:automatic-styles>
+  <office:body><office:text>
+    <text:h text:outline-level="1">Chapter One</text:h>
+    <text:p>It was<text:s text:c="2"/><text:span>dark</text:span>.<text:note><text:note-citation>1</text:note-citation><text:note-body><text:p>A footnote.</text:p></text:note-body></text:note></text:p>
+    <text:list><text:list-item><text:p>First</text:p></text:list-item></text:list>
+    <text:p/>
+  </office:text></office:body>
+</office:document-content>"#;
+
+        assert_eq!(odt_text(content), "Chapter One\n\nIt was  dark.\n\nFirst");
+    }
+
+    #[test]
+    fn reads_docx_file() {
+        let docx_file = tempfile::NamedTempFile::new().unwrap();
+
+        let mut writer = zip::ZipWriter::new(docx_file.reopen().unwrap());
+        writer
+            .start_file("word/document.xml", FileOptions::default())
+            .unwrap();
+        writer
+            .write_all(b"<w:document><w:body><w:p><w:r><w:t>Hello.</w:t></w:r></w:p></w:body></w:document>")
+            .unwrap();
+        writer.finish().unwrap();
+
+        assert_eq!(read_docx(docx_file.path()).unwrap().text, "Hello.");
+        assert!(read_odt(docx_file.path()).is_err());
+    }
+}
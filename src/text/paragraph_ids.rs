@@ -0,0 +1,65 @@
+//! Identifiers telling paragraphs apart by what they say and where they
+//! are, rather than by their position in the list of paragraphs.
+
+/// Gives every paragraph an identifier made from how much of the paragraphs
+/// before it there is and a hash of its words, which stays the same for as
+/// long as the paragraph is gathered the same way. Gathering can leave some
+/// of the text out, so that's not always where it starts in the text.
+pub fn paragraph_ids(paragraphs: &[String]) -> Vec<String> {
+    paragraphs
+        .iter()
+        .zip(paragraph_offsets(paragraphs))
+        .map(|(paragraph, offset)| format!("{offset}-{:016x}", words_hash(paragraph)))
+        .collect()
+}
+
+/// Returns how much of the paragraphs there is before each of them, the
+/// offset their identifiers are made from.
+pub fn paragraph_offsets(paragraphs: &[String]) -> Vec<usize> {
+    paragraphs
+        .iter()
+        .scan(0, |offset, paragraph| {
+            let paragraph_offset = *offset;
+            *offset += paragraph.len();
+
+            Some(paragraph_offset)
+        })
+        .collect()
+}
+
+/// Hashes the paragraph's words with 64-bit FNV-1a, which unlike the
+/// standard library's hasher is the same on every build, so identifiers
+/// saved in a session can be found again. Spacing is left out.
+fn words_hash(paragraph: &str) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+    paragraph
+        .split_whitespace()
+        .flat_map(|word| word.bytes().chain([b' ']))
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_follow_words_and_offsets() {
+        let paragraphs = [String::from("One. Two. "), String::from("Three.")];
+        let ids = paragraph_ids(&paragraphs);
+
+        // Saved in sessions, so they mustn't change between builds
+        assert_eq!(ids[0], "0-d64b89f82efe4e9b");
+        assert!(ids[1].starts_with("10-"));
+
+        // Spacing doesn't change a paragraph, but its words do
+        assert_eq!(words_hash("One.\n Two."), words_hash("One. Two. "));
+        assert_ne!(words_hash("One. Two."), words_hash("One. Too."));
+
+        let resplit = paragraph_ids(&[String::from("One. "), String::from("Two. Three.")]);
+        assert_ne!(ids[0], resplit[0]);
+    }
+}
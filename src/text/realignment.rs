@@ -0,0 +1,120 @@
+//! Matching the paragraphs of an edited text up with the ones it had
+//! before, so recordings of the paragraphs left alone can be kept.
+
+use similar::{capture_diff_slices, Algorithm, DiffOp, TextDiff};
+
+/// How alike a changed paragraph's words must be to the ones it had
+/// before, from 0 to 1, to still count as the same paragraph.
+const MIN_CHANGED_SIMILARITY: f32 = 0.5;
+
+/// Where a paragraph of the edited text came from.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Origin {
+    /// The paragraph at this position before, with the same words.
+    Unchanged(usize),
+    /// The paragraph at this position before, with some words changed.
+    Changed(usize),
+    /// Wasn't in the text before.
+    New,
+}
+
+/// Works out where each of the new paragraphs came from in the old ones.
+/// Only their words count, so reflowing a paragraph doesn't change it.
+pub fn match_paragraphs(old_paragraphs: &[String], new_paragraphs: &[String]) -> Vec<Origin> {
+    let old_words: Vec<String> = old_paragraphs.iter().map(|text| words(text)).collect();
+    let new_words: Vec<String> = new_paragraphs.iter().map(|text| words(text)).collect();
+
+    let mut origins = Vec::with_capacity(new_paragraphs.len());
+    for diff_op in capture_diff_slices(Algorithm::Myers, &old_words, &new_words) {
+        match diff_op {
+            DiffOp::Equal { old_index, len, .. } => {
+                origins.extend((old_index..old_index + len).map(Origin::Unchanged))
+            }
+            DiffOp::Delete { .. } => {}
+            DiffOp::Insert { new_len, .. } => {
+                origins.extend(std::iter::repeat_n(Origin::New, new_len));
+            }
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => origins.extend(match_replaced(
+                &old_words[old_index..old_index + old_len],
+                &new_words[new_index..new_index + new_len],
+                old_index,
+            )),
+        }
+    }
+
+    origins
+}
+
+/// Pairs each replacing paragraph, in order, with the first replaced one
+/// after the last pair that's still alike enough to be an edit of it.
+fn match_replaced(old_words: &[String], new_words: &[String], old_index: usize) -> Vec<Origin> {
+    let mut next_old = 0;
+
+    new_words
+        .iter()
+        .map(|new_paragraph| {
+            let edited = (next_old..old_words.len()).find(|&old_num| {
+                TextDiff::from_words(&old_words[old_num], new_paragraph).ratio()
+                    >= MIN_CHANGED_SIMILARITY
+            });
+
+            match edited {
+                Some(old_num) => {
+                    next_old = old_num + 1;
+                    Origin::Changed(old_index + old_num)
+                }
+                None => Origin::New,
+            }
+        })
+        .collect()
+}
+
+fn words(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_and_insertions_are_told_apart() {
+        let old_paragraphs = [
+            "It was a dark and stormy night. ",
+            "The rain fell in torrents, except\nat occasional intervals. ",
+            "It was checked by a violent gust of wind. ",
+            "Swept up the streets. ",
+        ]
+        .map(String::from);
+        let new_paragraphs = [
+            "It was a dark and stormy night. ",
+            "A brand new paragraph, written later. ",
+            "The rain fell in torrents, except at occasional intervals. ",
+            "It was checked by a violent gust of wind! ",
+            "Swept up the streets. ",
+        ]
+        .map(String::from);
+
+        assert_eq!(
+            match_paragraphs(&old_paragraphs, &new_paragraphs),
+            [
+                Origin::Unchanged(0),
+                Origin::New,
+                Origin::Unchanged(1),
+                Origin::Changed(2),
+                Origin::Unchanged(3)
+            ]
+        );
+
+        let rewritten = ["Nothing like before.", "Swept up the streets. "].map(String::from);
+        assert_eq!(
+            match_paragraphs(&old_paragraphs, &rewritten),
+            [Origin::New, Origin::Unchanged(3)]
+        );
+    }
+}
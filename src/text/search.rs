@@ -0,0 +1,130 @@
+//! Finding words in the paragraphs, regardless of their case.
+
+use regex::{Regex, RegexBuilder};
+
+/// Where in the paragraphs some text was found, by byte offsets into
+/// the paragraph it's in.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TextMatch {
+    pub paragraph_num: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub struct TextSearch {
+    pattern: Regex,
+}
+
+impl TextSearch {
+    /// Searches for the text as it's written, ignoring case, or returns
+    /// None if there's nothing to search for.
+    pub fn new(query: &str) -> Option<TextSearch> {
+        if query.is_empty() {
+            return None;
+        }
+
+        let pattern = RegexBuilder::new(&regex::escape(query))
+            .case_insensitive(true)
+            .build()
+            .expect("Escaped text should always be a valid regex.");
+
+        Some(TextSearch { pattern })
+    }
+
+    /// Finds the first match starting at or after the offset into the
+    /// paragraph, carrying on from the first paragraph after the last.
+    pub fn next(
+        &self,
+        paragraphs: &[String],
+        paragraph_num: usize,
+        offset: usize,
+    ) -> Option<TextMatch> {
+        let num_paragraphs = paragraphs.len();
+        if num_paragraphs == 0 {
+            return None;
+        }
+
+        // The paragraph searched from comes up again last, for what's before the offset
+        (0..=num_paragraphs).find_map(|step| {
+            let found_paragraph_num = (paragraph_num + step) % num_paragraphs;
+            self.pattern
+                .find_iter(&paragraphs[found_paragraph_num])
+                .find(|found| step > 0 || found.start() >= offset)
+                .map(|found| TextMatch {
+                    paragraph_num: found_paragraph_num,
+                    start: found.start(),
+                    end: found.end(),
+                })
+        })
+    }
+
+    /// Finds the last match starting before the offset into the paragraph,
+    /// carrying on from the last paragraph before the first.
+    pub fn previous(
+        &self,
+        paragraphs: &[String],
+        paragraph_num: usize,
+        offset: usize,
+    ) -> Option<TextMatch> {
+        let num_paragraphs = paragraphs.len();
+        if num_paragraphs == 0 {
+            return None;
+        }
+
+        (0..=num_paragraphs).find_map(|step| {
+            let found_paragraph_num = (paragraph_num + num_paragraphs - step) % num_paragraphs;
+            self.pattern
+                .find_iter(&paragraphs[found_paragraph_num])
+                .filter(|found| step > 0 || found.start() < offset)
+                .last()
+                .map(|found| TextMatch {
+                    paragraph_num: found_paragraph_num,
+                    start: found.start(),
+                    end: found.end(),
+                })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_ignore_case_and_wrap_around() {
+        let paragraphs = [
+            "She found the letter. ",
+            "Nothing here. ",
+            "The LETTER was opened, letter by letter.",
+        ]
+        .map(String::from);
+        let search = TextSearch::new("letter").unwrap();
+
+        let first = search.next(&paragraphs, 0, 0).unwrap();
+        assert_eq!(
+            first,
+            TextMatch {
+                paragraph_num: 0,
+                start: 14,
+                end: 20
+            }
+        );
+        assert_eq!(
+            search
+                .next(&paragraphs, 0, first.end)
+                .unwrap()
+                .paragraph_num,
+            2
+        );
+        assert_eq!(search.next(&paragraphs, 2, 35).unwrap(), first);
+
+        assert_eq!(search.previous(&paragraphs, 2, 4).unwrap(), first);
+        assert_eq!(search.previous(&paragraphs, 0, 14).unwrap().start, 33);
+
+        assert!(TextSearch::new("").is_none());
+        assert!(TextSearch::new("a.b")
+            .unwrap()
+            .next(&paragraphs, 0, 0)
+            .is_none());
+    }
+}
@@ -0,0 +1,200 @@
+//! Sentences found by their Unicode boundaries (UAX #29), which don't end at
+//! abbreviations like "Mr." or "e.g." nor at the end of a wrapped line.
+
+use std::collections::HashSet;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Languages with abbreviations known out of the box.
+pub const SENTENCE_LANGUAGES: [&str; 5] = ["English", "French", "German", "Spanish", "Italian"];
+
+const ENGLISH_ABBREVIATIONS: &str = "Mr. Mrs. Ms. Mx. Dr. Prof. Rev. Hon. Gen. Col. Capt. Lt. \
+    Sgt. Cpl. St. Mt. Ft. Jr. Sr. vs. e.g. i.e. cf. al. approx. dept. fig. vol. ch. pp. \
+    Inc. Ltd. Corp. Bros. Jan. Feb. Apr. Aug. Sept. Oct. Nov. Dec.";
+const FRENCH_ABBREVIATIONS: &str = "M. MM. Mme. Mmes. Mlle. Mlles. Dr. Pr. Me. Mgr. St. Ste. \
+    cf. p. ex. env. av. apr. J.-C. vol. chap. éd. fig.";
+const GERMAN_ABBREVIATIONS: &str = "Hr. Fr. Dr. Prof. bzw. usw. z. z.B. d.h. u.a. ca. vgl. \
+    Nr. Str. evtl. ggf. inkl. bspw. sog. St. Jh.";
+const SPANISH_ABBREVIATIONS: &str = "Sr. Sra. Srta. Dr. Dra. Ud. Uds. Dña. pág. p.ej. núm. \
+    av. aprox. Lic. Ing.";
+const ITALIAN_ABBREVIATIONS: &str =
+    "Sig. Sig.ra Sigg. Dott. Dott.ssa Prof. Avv. Ing. ecc. pag. ca. es. cfr.";
+
+/// Characters a sentence ends with, before any closing quotes or brackets.
+const SENTENCE_TERMINALS: [char; 8] = ['.', '!', '?', '…', '‽', '。', '！', '？'];
+
+/// Returns the abbreviations commonly written in the language, falling
+/// back to English ones for languages without a list of their own.
+pub fn default_abbreviations(language: &str) -> Vec<String> {
+    let abbreviations = match language {
+        "French" => FRENCH_ABBREVIATIONS,
+        "German" => GERMAN_ABBREVIATIONS,
+        "Spanish" => SPANISH_ABBREVIATIONS,
+        "Italian" => ITALIAN_ABBREVIATIONS,
+        _ => ENGLISH_ABBREVIATIONS,
+    };
+
+    abbreviations.split_whitespace().map(String::from).collect()
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct SentenceSplitter {
+    /// Lowercase, without their closing full stop.
+    abbreviations: HashSet<String>,
+}
+
+impl SentenceSplitter {
+    /// Splits sentences, except after any of the abbreviations, which may
+    /// be written with or without their closing full stop.
+    pub fn new(abbreviations: &[String]) -> Self {
+        SentenceSplitter {
+            abbreviations: abbreviations
+                .iter()
+                .map(|abbreviation| abbreviation.trim_end_matches('.').to_lowercase())
+                .filter(|abbreviation| !abbreviation.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Splits the text into sentences, each keeping the whitespace after it.
+    pub fn split<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let mut sentence_ends = Vec::new();
+        let mut sentence_start = 0;
+        let mut segment_end = 0;
+
+        for segment in text.split_sentence_bounds() {
+            segment_end += segment.len();
+            let sentence = &text[sentence_start..segment_end];
+
+            // Blank lines after a sentence still belong to it
+            let is_leftover_whitespace = sentence.trim().is_empty() && !sentence_ends.is_empty();
+            if is_leftover_whitespace {
+                sentence_ends.pop();
+            }
+
+            if is_leftover_whitespace || self.is_sentence_end(sentence) {
+                sentence_ends.push(segment_end);
+                sentence_start = segment_end;
+            }
+        }
+
+        if sentence_start < text.len() {
+            sentence_ends.push(text.len());
+        }
+
+        let mut sentence_start = 0;
+        sentence_ends
+            .into_iter()
+            .map(|sentence_end| {
+                let sentence = &text[sentence_start..sentence_end];
+                sentence_start = sentence_end;
+                sentence
+            })
+            .collect()
+    }
+
+    /// Unicode's boundaries also fall after abbreviations followed by a
+    /// capital, and after every line break, so only the ones following
+    /// a full sentence or closing a paragraph are kept.
+    fn is_sentence_end(&self, sentence: &str) -> bool {
+        let content = sentence.trim_end();
+        if content.is_empty() {
+            return false;
+        }
+        if sentence[content.len()..].matches('\n').count() > 1 {
+            return true;
+        }
+
+        let content = content.trim_end_matches(['"', '\'', '”', '’', '»', ')', ']']);
+        if !content.ends_with(SENTENCE_TERMINALS) {
+            return false;
+        }
+
+        !self.ends_with_abbreviation(content)
+    }
+
+    fn ends_with_abbreviation(&self, content: &str) -> bool {
+        let Some(last_word) = content
+            .split_whitespace()
+            .last()
+            .and_then(|word| word.strip_suffix('.'))
+        else {
+            return false;
+        };
+        let last_word =
+            last_word.trim_start_matches(|character: char| !character.is_alphanumeric());
+
+        // Initials, like the ones in "J. R. R. Tolkien"
+        let mut characters = last_word.chars();
+        if characters.next().is_some_and(char::is_uppercase) && characters.next().is_none() {
+            return true;
+        }
+
+        self.abbreviations.contains(&last_word.to_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abbreviations_decimals_and_ellipses_stay_in_sentences() {
+        let splitter = SentenceSplitter::new(&default_abbreviations("English"));
+        let text = "Mr. Smith met Dr. Jones, e.g. Monday. Pi is 3.14 or so... Isn't it? \
+            \"Yes.\" J. R. R. Tolkien agreed!";
+
+        assert_eq!(
+            splitter.split(text),
+            [
+                "Mr. Smith met Dr. Jones, e.g. Monday. ",
+                "Pi is 3.14 or so... ",
+                "Isn't it? ",
+                "\"Yes.\" ",
+                "J. R. R. Tolkien agreed!"
+            ]
+        );
+    }
+
+    #[test]
+    fn wrapped_lines_stay_in_sentences() {
+        let splitter = SentenceSplitter::default();
+        let text = "CHAPTER I\n\nIt was a dark\nand stormy night. The end\nwas near.\n";
+
+        assert_eq!(
+            splitter.split(text),
+            [
+                "CHAPTER I\n\n",
+                "It was a dark\nand stormy night. ",
+                "The end\nwas near.\n"
+            ]
+        );
+        assert_eq!(splitter.split(text).concat(), text);
+    }
+
+    #[test]
+    fn blank_lines_stay_with_sentence_before() {
+        let splitter = SentenceSplitter::default();
+        let text = "\nThe end\nwas near.\n\n\nNext.";
+
+        assert_eq!(
+            splitter.split(text),
+            ["\nThe end\nwas near.\n\n\n", "Next."]
+        );
+        assert_eq!(splitter.split(text).concat(), text);
+    }
+
+    #[test]
+    fn abbreviations_depend_on_language() {
+        let text = "Das ist z.B. Herr Hr. Müller. Gut.";
+
+        assert_eq!(
+            SentenceSplitter::new(&default_abbreviations("German")).split(text),
+            ["Das ist z.B. Herr Hr. Müller. ", "Gut."]
+        );
+        assert_eq!(
+            SentenceSplitter::new(&default_abbreviations("English")).split(text),
+            ["Das ist z.B. ", "Herr Hr. ", "Müller. ", "Gut."]
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use zip::ZipArchive;
+
+use crate::text::chapters::ChapterBreak;
+use crate::text::encoding::read_text;
+use crate::text::epub::read_epub;
+use crate::text::fountain::read_fountain;
+use crate::text::markdown::markdown_to_text;
+use crate::text::markup::html_to_text;
+use crate::text::office::{read_docx, read_odt};
+
+/// Text to be read out, along with where its chapters start when the
+/// file it came from already says so.
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct SourceText {
+    pub text: String,
+    /// Empty when chapters are left to be found by their headings.
+    pub chapter_breaks: Vec<ChapterBreak>,
+    /// Empty when the text is to be gathered into paragraphs by sentence.
+    pub passages: Vec<Passage>,
+}
+
+/// Where a passage the file sets apart to be read on its own starts,
+/// like a line of dialogue in a script.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Passage {
+    /// Byte offset of the passage's start in the text.
+    pub offset: usize,
+    /// Who the passage is spoken by, if anyone in particular.
+    pub speaker: Option<String>,
+}
+
+/// Choices about what gets read out of a file.
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct SourceOptions {
+    /// Whether blocks of code in Markdown or HTML are kept, rather than left out.
+    pub read_code_blocks: bool,
+    /// The encoding plain text files are in, or None to work it out from the file.
+    pub encoding: Option<String>,
+}
+
+/// Reads the text of a file, picking how by its extension. Anything
+/// that isn't a known format is read as plain text.
+pub fn read_source(source_file: &Path, options: &SourceOptions) -> Result<SourceText> {
+    let read_plain_text = || read_text(source_file, options.encoding.as_deref());
+
+    let extension = source_file
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+
+    match extension.as_deref() {
+        Some("epub") => read_epub(source_file, options),
+        Some("md" | "markdown") => {
+            let markdown = read_plain_text()?;
+            Ok(markdown_to_text(&markdown, options.read_code_blocks))
+        }
+        Some("docx") => read_docx(source_file),
+        Some("odt") => read_odt(source_file),
+        Some("html" | "htm" | "xhtml") => {
+            let html_text = html_to_text(&read_plain_text()?, options.read_code_blocks);
+
+            Ok(SourceText {
+                text: html_text.text,
+                chapter_breaks: html_text.headings,
+                passages: Vec::new(),
+            })
+        }
+        Some("fountain" | "spmd") => Ok(read_fountain(&read_plain_text()?)),
+        _ => Ok(SourceText {
+            text: read_plain_text()?,
+            chapter_breaks: Vec::new(),
+            passages: Vec::new(),
+        }),
+    }
+}
+
+/// Reads a file packed inside a zipped format like EPUB or DOCX as text.
+pub fn read_archive_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    entry_name: &str,
+) -> Result<String> {
+    let mut entry = archive
+        .by_name(entry_name)
+        .with_context(|| format!("{entry_name} is missing from the file."))?;
+
+    let mut contents = String::new();
+    entry.read_to_string(&mut contents)?;
+
+    Ok(contents)
+}
@@ -1,11 +1,18 @@
-use std::fs::File;
-use std::io::Read;
 use std::path::PathBuf;
 
+use anyhow::Result;
+
 use fltk::button::Button;
+use fltk::enums::Color;
+use fltk::frame::Frame;
+use fltk::group::Flex;
 use fltk::prelude::{DisplayExt, WidgetExt};
-use fltk::text::TextDisplay;
+use fltk::text::{StyleTableEntry, TextBuffer, TextDisplay};
 
+use crate::text::chapters::{split_at_breaks, Chapter, ChapterBreak, ChapterDetector, ChapterText};
+use crate::text::gathering::{estimate_secs, Gathering};
+use crate::text::search::TextMatch;
+use crate::text::source::{read_source, Passage, SourceOptions};
 use crate::ui::app::ViewerWidgets;
 
 struct Counter {
@@ -13,6 +20,9 @@ struct Counter {
 
     current_pos: usize,
     total_elements: usize,
+    chapters: Vec<Chapter>,
+    /// How long each paragraph is estimated to take to read, if at all.
+    estimated_secs: Vec<usize>,
 }
 
 impl Counter {
@@ -21,6 +31,8 @@ impl Counter {
             progress_label,
             current_pos: 0,
             total_elements: 0,
+            chapters: Vec::new(),
+            estimated_secs: Vec::new(),
         }
     }
 
@@ -32,13 +44,57 @@ impl Counter {
         self.total_elements = total;
     }
 
+    pub fn set_chapters(&mut self, chapters: Vec<Chapter>) {
+        self.chapters = chapters;
+    }
+
+    pub fn chapters(&self) -> &[Chapter] {
+        &self.chapters
+    }
+
+    pub fn set_estimated_secs(&mut self, estimated_secs: Vec<usize>) {
+        self.estimated_secs = estimated_secs;
+    }
+
     pub fn update(&mut self) {
         if self.current_pos > self.total_elements {
             return;
         }
 
-        self.progress_label
-            .set_label(format!("{}/{}", self.current_pos, self.total_elements).as_str());
+        // Without any headings, the whole text is one chapter not worth mentioning
+        let current_chapter = self
+            .chapters
+            .iter()
+            .enumerate()
+            .find(|(_, chapter)| chapter.contains(self.current_pos.saturating_sub(1)))
+            .filter(|_| self.chapters.len() > 1);
+
+        let mut label = match current_chapter {
+            Some((chapter_num, chapter)) => {
+                self.progress_label
+                    .set_tooltip(chapter.title.as_deref().unwrap_or_default());
+                format!(
+                    "Chapter {} \u{2014} {}/{}",
+                    chapter_num + 1,
+                    self.current_pos - chapter.first_paragraph,
+                    chapter.num_paragraphs
+                )
+            }
+            None => {
+                self.progress_label.set_tooltip("");
+                format!("{}/{}", self.current_pos, self.total_elements)
+            }
+        };
+
+        if let Some(estimated_secs) = self.estimated_secs.get(self.current_pos.saturating_sub(1)) {
+            label.push_str(&format!(
+                "  (~{}:{:02})",
+                estimated_secs / 60,
+                estimated_secs % 60
+            ));
+        }
+
+        self.progress_label.set_label(&label);
     }
 
     pub fn at_beginning(&self) -> bool {
@@ -50,11 +106,112 @@ impl Counter {
     }
 }
 
+/// Style of text in the paragraph view, by the style table's entries.
+const PLAIN_STYLE: &str = "A";
+const FOUND_STYLE: &str = "B";
+
+/// Styles the whole paragraph plainly, a style for each of its bytes.
+fn plain_styles(paragraph: &str) -> String {
+    PLAIN_STYLE.repeat(paragraph.len())
+}
+
+/// Splits the chapters' text into paragraphs, gathering each chapter on
+/// its own so no paragraph continues past the chapter it started in.
+fn gather_paragraphs(
+    chapter_texts: Vec<ChapterText>,
+    gathering: &Gathering,
+) -> (Vec<String>, Vec<Chapter>) {
+    let mut paragraphs = Vec::new();
+    let mut chapters = Vec::new();
+    for chapter_text in chapter_texts {
+        let first_paragraph = paragraphs.len();
+        paragraphs.extend(gathering.paragraphs(chapter_text.text));
+
+        chapters.push(Chapter {
+            title: chapter_text.title,
+            first_paragraph,
+            num_paragraphs: paragraphs.len() - first_paragraph,
+        });
+    }
+
+    (paragraphs, chapters)
+}
+
+/// Splits the chapters' text at the start of every passage, so each
+/// passage is a paragraph of its own.
+fn gather_passages(
+    chapter_texts: Vec<ChapterText>,
+    passages: &[Passage],
+) -> (Vec<String>, Vec<Chapter>) {
+    let mut paragraphs = Vec::new();
+    let mut chapters = Vec::new();
+
+    let mut chapter_start = 0;
+    for chapter_text in chapter_texts {
+        let chapter_end = chapter_start + chapter_text.text.len();
+        let first_paragraph = paragraphs.len();
+
+        let mut paragraph_start = chapter_start;
+        for passage in passages
+            .iter()
+            .filter(|passage| passage.offset > chapter_start && passage.offset < chapter_end)
+        {
+            paragraphs.push(
+                chapter_text.text[paragraph_start - chapter_start..passage.offset - chapter_start]
+                    .to_string(),
+            );
+            paragraph_start = passage.offset;
+        }
+        paragraphs.push(chapter_text.text[paragraph_start - chapter_start..].to_string());
+
+        chapters.push(Chapter {
+            title: chapter_text.title,
+            first_paragraph,
+            num_paragraphs: paragraphs.len() - first_paragraph,
+        });
+        chapter_start = chapter_end;
+    }
+
+    (paragraphs, chapters)
+}
+
+/// Finds who speaks each paragraph, going by the passage it starts with.
+fn paragraph_speakers(paragraphs: &[String], passages: &[Passage]) -> Vec<Option<String>> {
+    let mut paragraph_start = 0;
+
+    paragraphs
+        .iter()
+        .map(|paragraph| {
+            let speaker = passages
+                .iter()
+                .find(|passage| passage.offset == paragraph_start)
+                .and_then(|passage| passage.speaker.clone());
+            paragraph_start += paragraph.len();
+
+            speaker
+        })
+        .collect()
+}
+
 pub struct ParagraphViewer {
+    /// All of the loaded text, which paragraphs are gathered from.
+    text: String,
     paragraphs: Vec<String>,
     paragraph_num: usize,
+    chapter_detector: ChapterDetector,
+    /// Where chapters start in the loaded text, if its file said so.
+    chapter_breaks: Vec<ChapterBreak>,
+    /// Set apart by the file itself, in which case they're the paragraphs.
+    passages: Vec<Passage>,
+    speakers: Vec<Option<String>>,
+    source_options: SourceOptions,
+    text_file_path: Option<PathBuf>,
 
     paragraph_view: TextDisplay,
+    /// Styles of the shown paragraph's text, to highlight found matches.
+    style_buffer: TextBuffer,
+    speaker_label: Frame,
+    viewer_layout: Flex,
     next_button: Button,
     prev_button: Button,
     progress_counter: Counter,
@@ -62,11 +219,34 @@ pub struct ParagraphViewer {
 
 impl ParagraphViewer {
     pub fn new(widgets: ViewerWidgets) -> Self {
+        let mut paragraph_view = widgets.paragraph_view;
+        let style_buffer = TextBuffer::default();
+        let plain_style = StyleTableEntry {
+            color: paragraph_view.text_color(),
+            font: paragraph_view.text_font(),
+            size: paragraph_view.text_size(),
+        };
+        let found_style = StyleTableEntry {
+            color: Color::Red,
+            ..plain_style
+        };
+        paragraph_view.set_highlight_data(style_buffer.clone(), vec![plain_style, found_style]);
+
         ParagraphViewer {
+            text: String::new(),
             paragraphs: Vec::new(),
             paragraph_num: 0,
-
-            paragraph_view: widgets.paragraph_view,
+            chapter_detector: ChapterDetector::default(),
+            chapter_breaks: Vec::new(),
+            passages: Vec::new(),
+            speakers: Vec::new(),
+            source_options: SourceOptions::default(),
+            text_file_path: None,
+
+            paragraph_view,
+            style_buffer,
+            speaker_label: widgets.speaker_label,
+            viewer_layout: widgets.viewer_layout,
             next_button: widgets.next_button,
             prev_button: widgets.prev_button,
             progress_counter: Counter::new(widgets.progress_counter),
@@ -87,47 +267,127 @@ impl ParagraphViewer {
         }
     }
 
-    pub fn load_paragraphs(&mut self, text_file_path: PathBuf, delimiters: &str, amount: usize) {
-        let mut text_file = File::open(text_file_path).expect("Could not load file.");
-        let mut whole_text_content = String::new();
-        text_file
-            .read_to_string(&mut whole_text_content)
-            .expect("Could not read text file.");
-
-        let delimiter_tokens = delimiters.chars().collect::<Vec<char>>();
-        let split_paragraphs: Vec<&str> = whole_text_content
-            .split_inclusive(&*delimiter_tokens)
-            .collect();
-
-        self.paragraphs = split_paragraphs
-            .chunks(amount)
-            .map(|sentences| sentences.concat())
-            .collect();
-
+    /// Reads the text file and gathers its text into paragraphs, leaving
+    /// the text shown so far as is when the file can't be read.
+    pub fn load_paragraphs(
+        &mut self,
+        text_file_path: PathBuf,
+        gathering: &Gathering,
+    ) -> Result<()> {
+        let source_text = read_source(&text_file_path, &self.source_options)?;
+        self.chapter_breaks = source_text.chapter_breaks;
+        self.passages = source_text.passages;
+        self.text_file_path = Some(text_file_path);
+
+        let (paragraphs, chapters) = self.gather(&source_text.text, gathering);
+        self.speakers = paragraph_speakers(&paragraphs, &self.passages);
+        self.paragraphs = paragraphs;
+        self.text = source_text.text;
+        self.estimate_durations(gathering);
+
+        self.progress_counter.set_chapters(chapters);
         self.progress_counter.set_current(0);
         self.progress_counter.set_total(self.paragraphs.len());
         self.progress_counter.update();
+
+        Ok(())
+    }
+
+    /// Splits the text at the chapters its file listed, or otherwise at
+    /// the headings found in it.
+    fn split_chapters<'a>(&self, text: &'a str) -> Vec<ChapterText<'a>> {
+        if self.chapter_breaks.is_empty() && self.passages.is_empty() {
+            self.chapter_detector.split(text)
+        } else {
+            split_at_breaks(text, &self.chapter_breaks)
+        }
+    }
+
+    /// Gathers the text into paragraphs, unless its file already set
+    /// passages apart to be read one at a time.
+    fn gather(&self, text: &str, gathering: &Gathering) -> (Vec<String>, Vec<Chapter>) {
+        let chapter_texts = self.split_chapters(text);
+
+        if self.passages.is_empty() {
+            gather_paragraphs(chapter_texts, gathering)
+        } else {
+            gather_passages(chapter_texts, &self.passages)
+        }
+    }
+
+    /// Works out how long each paragraph takes to read, for when they're
+    /// gathered by how long they take.
+    fn estimate_durations(&mut self, gathering: &Gathering) {
+        let estimated_secs = match gathering {
+            Gathering::Duration {
+                words_per_minute, ..
+            } => self
+                .paragraphs
+                .iter()
+                .map(|paragraph| estimate_secs(paragraph, *words_per_minute).round() as usize)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        self.progress_counter.set_estimated_secs(estimated_secs);
     }
 
-    /// Changes currently loaded text to be split by the provided
-    /// delimiters.
-    pub fn reload_text_with(&mut self, delimiters: &str, amount: usize) {
-        let existing_text = self.paragraphs.join("");
+    /// Shows who speaks the current paragraph above it, if anyone in particular.
+    fn update_speaker_label(&mut self) {
+        match self.speakers.get(self.paragraph_num).cloned().flatten() {
+            Some(speaker) => {
+                self.speaker_label.set_label(&speaker);
+                self.speaker_label.show();
+            }
+            None => self.speaker_label.hide(),
+        }
+
+        self.viewer_layout.layout();
+    }
+
+    /// Changes what gets read out of text files, such as code blocks.
+    /// Takes effect the next time paragraphs are loaded.
+    pub fn set_source_options(&mut self, source_options: SourceOptions) {
+        self.source_options = source_options;
+    }
+
+    pub fn source_options(&self) -> &SourceOptions {
+        &self.source_options
+    }
 
-        let delimiter_tokens = delimiters.chars().collect::<Vec<char>>();
-        let new_splitted_text: Vec<&str> =
-            existing_text.split_inclusive(&*delimiter_tokens).collect();
+    /// Changes which headings, besides the common ones, start a new chapter.
+    /// Takes effect the next time paragraphs are loaded or reloaded.
+    pub fn set_chapter_detector(&mut self, chapter_detector: ChapterDetector) {
+        self.chapter_detector = chapter_detector;
+    }
+
+    /// Reads the loaded text file again, for when what gets read out of
+    /// it has changed.
+    pub fn reload_source(&mut self, gathering: &Gathering) -> Result<()> {
+        let Some(text_file_path) = self.text_file_path.clone() else {
+            return Ok(());
+        };
+
+        self.load_paragraphs(text_file_path, gathering)?;
+        self.show_paragraph_at(0);
+
+        Ok(())
+    }
 
-        let new_chunked_text: Vec<String> = new_splitted_text
-            .chunks(amount)
-            .map(|line| line.concat())
-            .collect();
+    /// Changes currently loaded text to be gathered the provided way.
+    pub fn reload_text_with(&mut self, gathering: &Gathering) {
+        let (new_chunked_text, chapters) = self.gather(&self.text, gathering);
 
-        if new_chunked_text == self.paragraphs {
+        if new_chunked_text == self.paragraphs && chapters == self.progress_counter.chapters() {
+            self.estimate_durations(gathering);
+            self.progress_counter.update();
             return;
         }
 
+        self.speakers = paragraph_speakers(&new_chunked_text, &self.passages);
         self.paragraphs = new_chunked_text;
+        self.estimate_durations(gathering);
+        self.progress_counter.set_chapters(chapters);
 
         self.progress_counter.set_current(0);
         self.progress_counter.set_total(self.paragraphs.len());
@@ -143,6 +403,8 @@ impl ParagraphViewer {
                 .buffer()
                 .expect("Could not retrieve TextView")
                 .set_text(paragraph.as_str());
+            self.style_buffer.set_text(&plain_styles(paragraph));
+            self.update_speaker_label();
 
             self.progress_counter.set_current(self.paragraph_num);
             self.progress_counter.update();
@@ -164,6 +426,8 @@ impl ParagraphViewer {
                 .buffer()
                 .expect("Could not retrieve TextView")
                 .set_text(paragraph.as_str());
+            self.style_buffer.set_text(&plain_styles(paragraph));
+            self.update_speaker_label();
 
             self.progress_counter.set_current(self.paragraph_num);
             self.progress_counter.update();
@@ -183,6 +447,8 @@ impl ParagraphViewer {
                 .buffer()
                 .expect("Could not retrieve TextView")
                 .set_text(paragraph.as_str());
+            self.style_buffer.set_text(&plain_styles(paragraph));
+            self.update_speaker_label();
 
             self.progress_counter.set_current(self.paragraph_num);
             self.progress_counter.update();
@@ -193,6 +459,40 @@ impl ParagraphViewer {
         }
     }
 
+    /// Highlights a match found in the shown paragraph, scrolling to it.
+    pub fn highlight_match(&mut self, text_match: &TextMatch) {
+        let Some(paragraph) = self.paragraphs.get(self.paragraph_num) else {
+            return;
+        };
+        if text_match.paragraph_num != self.paragraph_num || text_match.end > paragraph.len() {
+            return;
+        }
+
+        let mut styles = plain_styles(paragraph);
+        styles.replace_range(
+            text_match.start..text_match.end,
+            &FOUND_STYLE.repeat(text_match.end - text_match.start),
+        );
+        self.style_buffer.set_text(&styles);
+
+        self.paragraph_view
+            .set_insert_position(text_match.start as i32);
+        self.paragraph_view.show_insert_position();
+    }
+
+    /// Returns all of the loaded text, including what paragraphs leave out.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn paragraphs(&self) -> &[String] {
+        &self.paragraphs
+    }
+
+    pub fn chapters(&self) -> &[Chapter] {
+        self.progress_counter.chapters()
+    }
+
     pub fn num_paragraphs(&self) -> usize {
         self.paragraphs.len()
     }
@@ -218,6 +518,13 @@ mod tests {
 
     const MANY_PARAGRAPHS_LEN: usize = 2;
 
+    fn delimited_gathering() -> Gathering {
+        Gathering::Delimited {
+            delimiters: String::from(DELIMITERS),
+            amount: GATHERING_AMOUNT,
+        }
+    }
+
     fn get_paragraph_viewer() -> ParagraphViewer {
         let main_application = MainApplication::new();
 
@@ -276,11 +583,12 @@ mod tests {
     #[test]
     fn goto_exceeds_paragraphs() {
         let mut paragraph_viewer = get_paragraph_viewer();
-        paragraph_viewer.load_paragraphs(
-            get_file_many_paragraphs().path().to_path_buf(),
-            DELIMITERS,
-            GATHERING_AMOUNT,
-        );
+        paragraph_viewer
+            .load_paragraphs(
+                get_file_many_paragraphs().path().to_path_buf(),
+                &delimited_gathering(),
+            )
+            .unwrap();
         assert_eq!(MANY_PARAGRAPHS_LEN, paragraph_viewer.num_paragraphs());
 
         let goto_paragraph_num = 3;
@@ -294,11 +602,12 @@ mod tests {
     #[test]
     fn goto_paragraph_exists() {
         let mut paragraph_viewer = get_paragraph_viewer();
-        paragraph_viewer.load_paragraphs(
-            get_file_many_paragraphs().path().to_path_buf(),
-            DELIMITERS,
-            GATHERING_AMOUNT,
-        );
+        paragraph_viewer
+            .load_paragraphs(
+                get_file_many_paragraphs().path().to_path_buf(),
+                &delimited_gathering(),
+            )
+            .unwrap();
         assert_eq!(MANY_PARAGRAPHS_LEN, paragraph_viewer.num_paragraphs());
 
         let goto_paragraph_num = 1;
@@ -329,11 +638,12 @@ mod tests {
     #[test]
     fn next_exceeds_paragraphs() {
         let mut paragraph_viewer = get_paragraph_viewer();
-        paragraph_viewer.load_paragraphs(
-            get_file_one_paragraph().path().to_path_buf(),
-            DELIMITERS,
-            GATHERING_AMOUNT,
-        );
+        paragraph_viewer
+            .load_paragraphs(
+                get_file_one_paragraph().path().to_path_buf(),
+                &delimited_gathering(),
+            )
+            .unwrap();
         assert_eq!(1, paragraph_viewer.num_paragraphs());
 
         paragraph_viewer.show_paragraph_at(0);
@@ -350,11 +660,12 @@ mod tests {
     #[test]
     fn next_paragraph_exists() {
         let mut paragraph_viewer = get_paragraph_viewer();
-        paragraph_viewer.load_paragraphs(
-            get_file_many_paragraphs().path().to_path_buf(),
-            DELIMITERS,
-            GATHERING_AMOUNT,
-        );
+        paragraph_viewer
+            .load_paragraphs(
+                get_file_many_paragraphs().path().to_path_buf(),
+                &delimited_gathering(),
+            )
+            .unwrap();
         assert_eq!(MANY_PARAGRAPHS_LEN, paragraph_viewer.num_paragraphs());
 
         paragraph_viewer.show_paragraph_at(0);
@@ -387,11 +698,12 @@ mod tests {
     #[test]
     fn previous_negative_paragraphs() {
         let mut paragraph_viewer = get_paragraph_viewer();
-        paragraph_viewer.load_paragraphs(
-            get_file_one_paragraph().path().to_path_buf(),
-            DELIMITERS,
-            GATHERING_AMOUNT,
-        );
+        paragraph_viewer
+            .load_paragraphs(
+                get_file_one_paragraph().path().to_path_buf(),
+                &delimited_gathering(),
+            )
+            .unwrap();
         assert_eq!(1, paragraph_viewer.num_paragraphs());
 
         paragraph_viewer.show_paragraph_at(0);
@@ -408,11 +720,12 @@ mod tests {
     #[test]
     fn previous_paragraph_exists() {
         let mut paragraph_viewer = get_paragraph_viewer();
-        paragraph_viewer.load_paragraphs(
-            get_file_many_paragraphs().path().to_path_buf(),
-            DELIMITERS,
-            GATHERING_AMOUNT,
-        );
+        paragraph_viewer
+            .load_paragraphs(
+                get_file_many_paragraphs().path().to_path_buf(),
+                &delimited_gathering(),
+            )
+            .unwrap();
         assert_eq!(MANY_PARAGRAPHS_LEN, paragraph_viewer.num_paragraphs());
 
         paragraph_viewer.show_paragraph_at(MANY_PARAGRAPHS_LEN - 1);
@@ -433,11 +746,12 @@ mod tests {
     #[test]
     fn shows_paragraph() {
         let mut paragraph_viewer = get_paragraph_viewer();
-        paragraph_viewer.load_paragraphs(
-            get_file_one_paragraph().path().to_path_buf(),
-            DELIMITERS,
-            GATHERING_AMOUNT,
-        );
+        paragraph_viewer
+            .load_paragraphs(
+                get_file_one_paragraph().path().to_path_buf(),
+                &delimited_gathering(),
+            )
+            .unwrap();
         assert_eq!(1, paragraph_viewer.num_paragraphs());
 
         paragraph_viewer.show_paragraph_at(0);
@@ -447,4 +761,97 @@ mod tests {
             FIRST_PARAGRAPH
         );
     }
+
+    #[test]
+    fn load_unreadable_file() {
+        let mut paragraph_viewer = get_paragraph_viewer();
+        let missing_file = tempfile::tempdir()
+            .expect("Could not create temporary directory.")
+            .path()
+            .join("missing.txt");
+
+        assert!(paragraph_viewer
+            .load_paragraphs(missing_file, &delimited_gathering())
+            .is_err());
+        assert_eq!(0, paragraph_viewer.num_paragraphs());
+    }
+
+    #[test]
+    fn load_malformed_epub() {
+        let mut paragraph_viewer = get_paragraph_viewer();
+        let mut epub_file = tempfile::Builder::new()
+            .suffix(".epub")
+            .tempfile()
+            .expect("Could not create temporary file.");
+        epub_file
+            .write_all(b"Not a zip archive.")
+            .expect("Could not write to temporary file.");
+
+        assert!(paragraph_viewer
+            .load_paragraphs(epub_file.path().to_path_buf(), &delimited_gathering())
+            .is_err());
+        assert_eq!(0, paragraph_viewer.num_paragraphs());
+    }
+
+    #[test]
+    fn paragraphs_stay_within_chapters() {
+        let text = "CHAPTER I\nOne. Two. Three.\n\nCHAPTER II\nFour. Five.\n";
+        let gathering = Gathering::Delimited {
+            delimiters: String::from(DELIMITERS),
+            amount: 2,
+        };
+        let (paragraphs, chapters) =
+            gather_paragraphs(ChapterDetector::default().split(text), &gathering);
+
+        assert_eq!(
+            paragraphs,
+            [
+                "CHAPTER I\nOne. Two.",
+                " Three.\n\n",
+                "CHAPTER II\nFour. Five.\n"
+            ]
+        );
+        assert_eq!(paragraphs.concat(), text);
+
+        assert_eq!(chapters.len(), 2);
+        assert_eq!(chapters[1].title.as_deref(), Some("CHAPTER II"));
+        assert_eq!(chapters[1].first_paragraph, 2);
+        assert_eq!(chapters[1].num_paragraphs, 1);
+    }
+
+    #[test]
+    fn passages_are_paragraphs() {
+        let text = "INT. HOUSE\n\nHello. Hi.\n\nA door slams.";
+        let passages = [
+            Passage {
+                offset: 0,
+                speaker: None,
+            },
+            Passage {
+                offset: 12,
+                speaker: Some(String::from("ANN")),
+            },
+            Passage {
+                offset: 24,
+                speaker: None,
+            },
+        ];
+        let breaks = [ChapterBreak {
+            offset: 0,
+            title: String::from("INT. HOUSE"),
+        }];
+
+        let (paragraphs, chapters) = gather_passages(split_at_breaks(text, &breaks), &passages);
+
+        assert_eq!(
+            paragraphs,
+            ["INT. HOUSE\n\n", "Hello. Hi.\n\n", "A door slams."]
+        );
+        assert_eq!(chapters.len(), 1);
+        assert_eq!(chapters[0].num_paragraphs, 3);
+        assert_eq!(
+            paragraph_speakers(&paragraphs, &passages),
+            [None, Some(String::from("ANN")), None]
+        );
+    }
 }
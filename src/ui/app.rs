@@ -1,23 +1,47 @@
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use anyhow::Result;
 
 use fltk::{
     app::{self, App},
-    button::Button,
+    button::{Button, CheckButton},
     dialog,
-    enums::{Align, FrameType, Shortcut},
+    enums::{Align, CallbackTrigger, Font, FrameType, Shortcut},
     frame::Frame,
     group::{self, Flex},
     image,
-    menu::{self, MenuItem, SysMenuBar},
+    input::MultilineInput,
+    menu::{self, Choice, MenuItem, SysMenuBar},
     prelude::*,
     text::{self, TextBuffer, TextDisplay, WrapMode},
     valuator::HorNiceSlider,
     window::Window,
 };
 
-use crate::{media::io::Media, sessions::session::Session, text::viewer::ParagraphViewer};
+use crate::{
+    media::{
+        durations::DurationCache,
+        export::{export_recordings, ExportReport},
+        io::{check_punch_in_format, Media, PunchIn},
+    },
+    sessions::{session::Session, status::ParagraphStatus},
+    text::{
+        chapters::ChapterDetector,
+        search::{TextMatch, TextSearch},
+        viewer::ParagraphViewer,
+    },
+};
 
-use super::dialogs::{about::AboutDialog, goto::GotoPrompt, preferences::PreferencesDialog};
+use super::{
+    dialogs::{
+        about::AboutDialog, find::FindDialog, goto::GotoPrompt, preferences::PreferencesDialog,
+    },
+    meter::LevelMeter,
+    overview::{Overview, OverviewEntry},
+    waveform::Waveform,
+};
 
 #[derive(Copy, Clone)]
 pub enum UIActions {
@@ -26,10 +50,30 @@ pub enum UIActions {
     Play,
     Stop,
     Record,
+    RecordingStarted,
+    RecordingFailed,
+    SelectTake,
     AudioSkip(usize),
+    MarkRetake,
+    MarkApproved,
+    NextWithStatus,
+    PreviousWithStatus,
+    ShowParagraph(usize),
+    ToggleOverview,
+    DurationsMeasured,
+    EditNote,
+    AddBookmark,
+    GoToBookmark(usize),
+    RemoveBookmark(usize),
+    GoToChapter(usize),
 
     OpenGoto,
+    OpenFind,
+    FindNext,
+    FindPrevious,
     LoadFile,
+    Export,
+    ExportFinished,
     //LoadRecent(String),
     OpenPreferences,
 
@@ -37,8 +81,32 @@ pub enum UIActions {
     Quit,
 }
 
+impl UIActions {
+    /// Whether the action can show another paragraph, or split the text
+    /// differently, which mustn't happen while its recording is in use.
+    fn changes_paragraph(self) -> bool {
+        matches!(
+            self,
+            UIActions::Next
+                | UIActions::Previous
+                | UIActions::NextWithStatus
+                | UIActions::PreviousWithStatus
+                | UIActions::ShowParagraph(_)
+                | UIActions::GoToBookmark(_)
+                | UIActions::GoToChapter(_)
+                | UIActions::OpenGoto
+                | UIActions::FindNext
+                | UIActions::FindPrevious
+                | UIActions::LoadFile
+                | UIActions::OpenPreferences
+        )
+    }
+}
+
 pub struct ViewerWidgets {
     pub paragraph_view: TextDisplay,
+    pub speaker_label: Frame,
+    pub viewer_layout: Flex,
     pub next_button: Button,
     pub prev_button: Button,
 
@@ -46,6 +114,7 @@ pub struct ViewerWidgets {
 }
 
 pub struct MediaTrackingWidgets {
+    pub waveform: Waveform,
     pub progress_bar: HorNiceSlider,
     pub time_progress_label: Frame,
     pub status_bar: TextDisplay,
@@ -60,27 +129,70 @@ pub struct MainUIWidgets {
     pub play_button: Button,
     pub stop_button: Button,
     pub record_button: Button,
+    pub take_selector: Choice,
+    pub punch_in_toggle: CheckButton,
+    pub level_meter: LevelMeter,
+
+    pub status_label: Frame,
+    pub retake_toggle: CheckButton,
+    pub approved_toggle: CheckButton,
+    pub status_filter: Choice,
+    pub overview: Overview,
+    pub notes_input: MultilineInput,
 
     pub next_button: Button,
     pub prev_button: Button,
 }
 
+/// An export running in the background, until it's done and reported on.
+pub struct ExportJob {
+    export_location: PathBuf,
+    /// The paragraphs as they were when exporting started, for listing
+    /// the unrecorded ones.
+    paragraphs: Vec<String>,
+    handle: JoinHandle<Result<ExportReport>>,
+}
+
 pub struct MainApplication {
     pub app: App,
     pub main_window: Window,
     pub ui_action_receiver: fltk::app::Receiver<UIActions>,
+    pub ui_action_sender: fltk::app::Sender<UIActions>,
 
     // Widgets
     pub paragraph_viewer: ParagraphViewer,
     pub media_io: Media,
+    pub take_selector: Choice,
+    pub punch_in_toggle: CheckButton,
+    pub status_label: Frame,
+    pub retake_toggle: CheckButton,
+    pub approved_toggle: CheckButton,
+    pub status_filter: Choice,
+    pub overview: Overview,
+    pub notes_input: MultilineInput,
+    pub menu_bar: SysMenuBar,
 
     // Dialogs
     pub goto_dialog: GotoPrompt,
+    pub find_dialog: FindDialog,
     pub about_dialog: AboutDialog,
     pub preferences_dialog: PreferencesDialog,
 
     // State
     pub session: Option<Session>,
+    /// The match found last, which the next search carries on from.
+    pub last_match: Option<TextMatch>,
+    /// The paragraph being recorded and where its new take goes, until
+    /// recording has started and the take can be kept.
+    pub pending_take: Option<(usize, PathBuf)>,
+    pub export_job: Option<ExportJob>,
+    /// How long recordings listed in the overview are, which is worked out
+    /// in the background the first time they're listed.
+    pub durations: Arc<Mutex<DurationCache>>,
+    pub is_measuring_durations: bool,
+    /// Whether the overview was refreshed while measuring, with more
+    /// recordings to measure after.
+    pub remeasure_durations: bool,
 }
 
 impl MainApplication {
@@ -112,26 +224,52 @@ impl MainApplication {
         main_window.set_icon(Some(window_icon));
         main_window.set_icon_label("Narrative Director");
 
+        let take_selector = ui_widgets.take_selector.clone();
+        let punch_in_toggle = ui_widgets.punch_in_toggle.clone();
+        let status_label = ui_widgets.status_label.clone();
+        let retake_toggle = ui_widgets.retake_toggle.clone();
+        let approved_toggle = ui_widgets.approved_toggle.clone();
+        let status_filter = ui_widgets.status_filter.clone();
+        let overview = ui_widgets.overview.clone();
+        let notes_input = ui_widgets.notes_input.clone();
+
         MainApplication {
             app,
             main_window,
             ui_action_receiver: receiver,
+            ui_action_sender: broadcaster,
 
             paragraph_viewer: ParagraphViewer::new(viewer_widgets),
-            media_io: Media::new(ui_widgets, media_tracking_widgets),
+            media_io: Media::new(&broadcaster, ui_widgets, media_tracking_widgets),
+            take_selector,
+            punch_in_toggle,
+            status_label,
+            retake_toggle,
+            approved_toggle,
+            status_filter,
+            overview,
+            notes_input,
+            menu_bar,
 
             goto_dialog: GotoPrompt::new(),
+            find_dialog: FindDialog::new(&broadcaster),
             about_dialog: AboutDialog::new(),
             preferences_dialog: PreferencesDialog::new(),
 
             session: None,
+            last_match: None,
+            pending_take: None,
+            export_job: None,
+            durations: Arc::new(Mutex::new(DurationCache::default())),
+            is_measuring_durations: false,
+            remeasure_durations: false,
         }
     }
 
     fn open(&self) -> Option<PathBuf> {
         let mut file_chooser =
             dialog::NativeFileChooser::new(dialog::NativeFileChooserType::BrowseFile);
-        file_chooser.set_filter("*.txt");
+        file_chooser.set_filter("*.{txt,epub,md,markdown,html,htm,docx,odt,fountain}");
         file_chooser.show();
 
         let filename = file_chooser.filename();
@@ -143,17 +281,479 @@ impl MainApplication {
         None
     }
 
+    /// Starts exporting the active take of every paragraph into a single
+    /// audio file chosen by the user. It's done in the background, so the
+    /// window keeps responding while big projects are exported.
+    fn export(&mut self) {
+        if self.export_job.is_some() {
+            dialog::alert_default("The project is still being exported.");
+            return;
+        }
+        let Some(session) = &self.session else {
+            return;
+        };
+
+        let mut file_chooser =
+            dialog::NativeFileChooser::new(dialog::NativeFileChooserType::BrowseSaveFile);
+        file_chooser.set_option(dialog::NativeFileChooserOptions::SaveAsConfirm);
+        file_chooser.set_filter("*.wav");
+        file_chooser.set_preset_file(&format!("{}.wav", session.project_name()));
+        file_chooser.show();
+
+        let mut export_location = file_chooser.filename();
+        if export_location.as_os_str().is_empty() {
+            return;
+        }
+        if export_location.extension().is_none() {
+            export_location.set_extension("wav");
+        }
+
+        let recordings: Vec<Option<PathBuf>> = (0..self.paragraph_viewer.num_paragraphs())
+            .map(|paragraph_num| session.active_take(paragraph_num))
+            .collect();
+
+        let gap_ms = session.export_gap_ms();
+        let output_file = export_location.clone();
+        let action_sender = self.ui_action_sender;
+        let handle = thread::spawn(move || {
+            let report = export_recordings(&recordings, gap_ms, &output_file);
+            action_sender.send(UIActions::ExportFinished);
+
+            report
+        });
+
+        self.export_job = Some(ExportJob {
+            export_location,
+            paragraphs: self.paragraph_viewer.paragraphs().to_vec(),
+            handle,
+        });
+    }
+
+    /// Tells the user how the export went, along with writing a report of
+    /// the paragraphs left out for being unrecorded.
+    fn finish_export(&mut self) {
+        let Some(export_job) = self.export_job.take() else {
+            return;
+        };
+        let export_location = export_job.export_location;
+
+        let report = match export_job.handle.join() {
+            Ok(Ok(report)) => report,
+            Ok(Err(error)) => {
+                dialog::alert_default(&format!("Could not export the project: {error}"));
+                return;
+            }
+            Err(_) => {
+                dialog::alert_default(
+                    "Could not export the project: exporting stopped unexpectedly.",
+                );
+                return;
+            }
+        };
+
+        if report.unrecorded.is_empty() {
+            dialog::message_default(&format!(
+                "Exported {} paragraphs to {}.",
+                report.num_exported,
+                export_location.display()
+            ));
+            return;
+        }
+
+        let report_location = export_location.with_extension("unrecorded.txt");
+        let summary = report.unrecorded_summary(&export_job.paragraphs);
+        let report_written = std::fs::write(&report_location, summary).is_ok();
+
+        dialog::message_default(&format!(
+            "Exported {} paragraphs to {}.\n\n{} paragraphs have no recording and were left out.{}",
+            report.num_exported,
+            export_location.display(),
+            report.unrecorded.len(),
+            if report_written {
+                format!(" They are listed in {}.", report_location.display())
+            } else {
+                String::new()
+            }
+        ));
+    }
+
     fn load_audio_file(&mut self) {
+        let paragraph_num = self.paragraph_viewer.paragraph_num();
         let current_session = self
             .session
             .as_ref()
             .expect("A session must exist if Next messages can be processed.");
 
-        let audio_file_location = current_session
-            .project_directory()
-            .join(format!("part{}.wav", self.paragraph_viewer.paragraph_num()));
+        self.take_selector.clear();
+        if let Some(takes) = current_session.takes(paragraph_num) {
+            for take_num in 1..=takes.len() {
+                self.take_selector.add_choice(&format!("Take {take_num}"));
+            }
+
+            self.take_selector.set_value(takes.active() as i32);
+        }
+
+        if self.take_selector.size() > 0 {
+            self.take_selector.activate();
+        } else {
+            self.take_selector.deactivate();
+        }
+
+        match current_session.active_take(paragraph_num) {
+            Some(audio_file_location) => self.media_io.load(audio_file_location),
+            None => self
+                .media_io
+                .unload(self.paragraph_viewer.num_paragraphs() > 0),
+        }
+
+        self.show_status();
+        self.show_note();
+    }
+
+    /// Shows the note left on the current paragraph, for reading or editing.
+    fn show_note(&mut self) {
+        let Some(session) = &self.session else {
+            return;
+        };
+
+        self.notes_input
+            .set_value(&session.note(self.paragraph_viewer.paragraph_num()));
+        self.notes_input.activate();
+    }
+
+    /// Bookmarks the current paragraph by a name the user chooses.
+    fn add_bookmark(&mut self) {
+        let paragraph_num = self.paragraph_viewer.paragraph_num();
+        let Some(session) = self.session.as_mut() else {
+            return;
+        };
+
+        let Some(name) = dialog::input_default(
+            "Bookmark name:",
+            &format!("Paragraph {}", paragraph_num + 1),
+        ) else {
+            return;
+        };
+        if name.trim().is_empty() {
+            return;
+        }
+
+        session.add_bookmark(name.trim(), paragraph_num);
+        self.update_bookmarks_menu();
+    }
+
+    fn go_to_bookmark(&mut self, bookmark_index: usize) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        let Some(name) = session.bookmark_names().get(bookmark_index).cloned() else {
+            return;
+        };
+
+        match session.bookmark(&name) {
+            Some(paragraph_num) => {
+                self.paragraph_viewer.show_paragraph_at(paragraph_num);
+                self.load_audio_file();
+            }
+            None => dialog::alert_default(&format!(
+                "The paragraph bookmarked as \"{name}\" isn't in the text the way it's split now."
+            )),
+        }
+    }
+
+    /// Lists the session's bookmarks in the Bookmarks menu, to be gone to
+    /// or removed.
+    fn update_bookmarks_menu(&mut self) {
+        let bookmark_names = self
+            .session
+            .as_ref()
+            .map(Session::bookmark_names)
+            .unwrap_or_default();
+
+        for submenu_name in ["&Bookmarks/Go To", "&Bookmarks/Remove"] {
+            let submenu_index = self.menu_bar.find_index(submenu_name);
+            if submenu_index >= 0 {
+                self.menu_bar.remove(submenu_index);
+            }
 
-        self.media_io.load(audio_file_location);
+            if bookmark_names.is_empty() {
+                self.menu_bar.add(
+                    &format!("{submenu_name}/No Bookmarks"),
+                    Shortcut::None,
+                    menu::MenuFlag::Inactive,
+                    |_| {},
+                );
+            }
+        }
+
+        for (bookmark_index, name) in bookmark_names.iter().enumerate() {
+            self.menu_bar.add_emit(
+                &format!("&Bookmarks/Go To/{}", menu_label(name)),
+                Shortcut::None,
+                menu::MenuFlag::Normal,
+                self.ui_action_sender,
+                UIActions::GoToBookmark(bookmark_index),
+            );
+            self.menu_bar.add_emit(
+                &format!("&Bookmarks/Remove/{}", menu_label(name)),
+                Shortcut::None,
+                menu::MenuFlag::Normal,
+                self.ui_action_sender,
+                UIActions::RemoveBookmark(bookmark_index),
+            );
+        }
+    }
+
+    /// Lists the chapters of the text in the View menu, to be gone to.
+    fn update_chapters_menu(&mut self) {
+        let chapters = self
+            .session
+            .as_ref()
+            .map(Session::chapters)
+            .unwrap_or_default();
+
+        let submenu_index = self.menu_bar.find_index("&View/Chapters");
+        if submenu_index >= 0 {
+            self.menu_bar.remove(submenu_index);
+        }
+
+        if chapters.is_empty() {
+            self.menu_bar.add(
+                "&View/Chapters/No Chapters",
+                Shortcut::None,
+                menu::MenuFlag::Inactive,
+                |_| {},
+            );
+        }
+
+        for (chapter_num, chapter) in chapters.iter().enumerate() {
+            let title = chapter
+                .title
+                .as_deref()
+                .unwrap_or("Before the first chapter");
+
+            self.menu_bar.add_emit(
+                &format!(
+                    "&View/Chapters/{}",
+                    menu_label(&format!("{}. {title}", chapter_num + 1))
+                ),
+                Shortcut::None,
+                menu::MenuFlag::Normal,
+                self.ui_action_sender,
+                UIActions::GoToChapter(chapter_num),
+            );
+        }
+    }
+
+    /// Shows the current paragraph's status, checking the toggle of the
+    /// one it was marked with, if any.
+    fn show_status(&mut self) {
+        let Some(session) = &self.session else {
+            return;
+        };
+
+        let paragraph_num = self.paragraph_viewer.paragraph_num();
+        let status = session.status(paragraph_num);
+        self.status_label.set_label(status.name());
+        self.retake_toggle
+            .set_checked(status == ParagraphStatus::Retake);
+        self.approved_toggle
+            .set_checked(status == ParagraphStatus::Approved);
+
+        self.retake_toggle.activate();
+        // Only a take can be approved
+        if session
+            .takes(paragraph_num)
+            .is_some_and(|takes| !takes.is_empty())
+        {
+            self.approved_toggle.activate();
+        } else {
+            self.approved_toggle.deactivate();
+        }
+
+        self.update_overview_entry();
+    }
+
+    /// Finds the next, or previous, match of the text searched for, going
+    /// to its paragraph and highlighting it.
+    fn find(&mut self, forward: bool) {
+        let Some(text_search) = TextSearch::new(&self.find_dialog.query()) else {
+            return;
+        };
+        let paragraphs = self.paragraph_viewer.paragraphs();
+        let paragraph_num = self.paragraph_viewer.paragraph_num();
+
+        // Carries on from the last match while its paragraph is still shown
+        let shown_match = self
+            .last_match
+            .filter(|last_match| last_match.paragraph_num == paragraph_num);
+        let found_match = match (forward, shown_match) {
+            (true, Some(last_match)) => text_search.next(paragraphs, paragraph_num, last_match.end),
+            (true, None) => text_search.next(paragraphs, paragraph_num, 0),
+            (false, Some(last_match)) => {
+                text_search.previous(paragraphs, paragraph_num, last_match.start)
+            }
+            (false, None) => text_search.previous(
+                paragraphs,
+                paragraph_num,
+                paragraphs.get(paragraph_num).map_or(0, String::len),
+            ),
+        };
+
+        let Some(found_match) = found_match else {
+            self.find_dialog.set_result("No matches found.");
+            return;
+        };
+
+        if found_match.paragraph_num != paragraph_num {
+            self.paragraph_viewer
+                .show_paragraph_at(found_match.paragraph_num);
+            self.load_audio_file();
+        }
+        self.paragraph_viewer.highlight_match(&found_match);
+        self.find_dialog.set_result(&format!(
+            "Found in paragraph {}.",
+            found_match.paragraph_num + 1
+        ));
+        self.last_match = Some(found_match);
+    }
+
+    /// Lists every paragraph in the overview, if it's shown. Recordings
+    /// that haven't been measured yet are listed without a duration until
+    /// they are, which is done in the background.
+    fn refresh_overview(&mut self) {
+        let unmeasured_recordings = self.fill_overview();
+        if unmeasured_recordings.is_empty() {
+            return;
+        }
+        if self.is_measuring_durations {
+            self.remeasure_durations = true;
+            return;
+        }
+
+        self.is_measuring_durations = true;
+        let durations = self.durations.clone();
+        let action_sender = self.ui_action_sender;
+        thread::spawn(move || {
+            for take_location in unmeasured_recordings {
+                durations
+                    .lock()
+                    .expect("Could not keep recording durations.")
+                    .measure(&take_location);
+            }
+
+            action_sender.send(UIActions::DurationsMeasured);
+        });
+    }
+
+    /// Lists every paragraph in the overview with the durations measured
+    /// so far, if it's shown. Returns the recordings left to measure.
+    fn fill_overview(&mut self) -> Vec<PathBuf> {
+        if !self.overview.is_shown() {
+            return Vec::new();
+        }
+        let Some(session) = &self.session else {
+            return Vec::new();
+        };
+
+        let durations = self
+            .durations
+            .lock()
+            .expect("Could not read recording durations.");
+        let mut unmeasured_recordings = Vec::new();
+        let entries = self
+            .paragraph_viewer
+            .paragraphs()
+            .iter()
+            .enumerate()
+            .map(|(paragraph_num, paragraph)| {
+                let active_take = session.active_take(paragraph_num);
+                let duration_ms = active_take
+                    .as_deref()
+                    .and_then(|take_location| durations.get(take_location));
+                if let Some(take_location) =
+                    active_take.filter(|take_location| durations.needs_measuring(take_location))
+                {
+                    unmeasured_recordings.push(take_location);
+                }
+
+                OverviewEntry::new(paragraph, session.status(paragraph_num), duration_ms)
+            })
+            .collect();
+        drop(durations);
+
+        self.overview.set_entries(entries);
+        self.overview.select(self.paragraph_viewer.paragraph_num());
+
+        unmeasured_recordings
+    }
+
+    /// Lists the current paragraph in the overview as it is now, if shown.
+    fn update_overview_entry(&mut self) {
+        if !self.overview.is_shown() {
+            return;
+        }
+        let Some(session) = &self.session else {
+            return;
+        };
+
+        let paragraph_num = self.paragraph_viewer.paragraph_num();
+        if let Some(paragraph) = self.paragraph_viewer.paragraphs().get(paragraph_num) {
+            let duration_ms = session
+                .active_take(paragraph_num)
+                .and_then(|take_location| {
+                    self.durations
+                        .lock()
+                        .expect("Could not keep recording durations.")
+                        .measure(&take_location)
+                });
+
+            self.overview.set_entry(
+                paragraph_num,
+                OverviewEntry::new(paragraph, session.status(paragraph_num), duration_ms),
+            );
+        }
+        self.overview.select(paragraph_num);
+    }
+
+    /// Marks the current paragraph with the status, or takes the mark off.
+    fn mark_status(&mut self, status: ParagraphStatus, marked: bool) {
+        let paragraph_num = self.paragraph_viewer.paragraph_num();
+        if let Some(session) = self.session.as_mut() {
+            session.set_status(paragraph_num, marked.then_some(status));
+        }
+
+        self.show_status();
+    }
+
+    /// Goes to the closest paragraph after, or before, the current one
+    /// with the status chosen to look for.
+    fn show_paragraph_with_status(&mut self, forward: bool) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        let Some(&status) = ParagraphStatus::ALL.get(self.status_filter.value() as usize) else {
+            return;
+        };
+
+        let paragraph_num = self.paragraph_viewer.paragraph_num();
+        let found_paragraph_num = if forward {
+            session.next_with_status(paragraph_num, status)
+        } else {
+            session.previous_with_status(paragraph_num, status)
+        };
+
+        match found_paragraph_num {
+            Some(found_paragraph_num) => {
+                self.paragraph_viewer.show_paragraph_at(found_paragraph_num);
+                self.load_audio_file();
+            }
+            None => dialog::message_default(&format!(
+                "No paragraph {} this one is {}.",
+                if forward { "after" } else { "before" },
+                status.name()
+            )),
+        }
     }
 
     fn load_text_file(&mut self, file_location: PathBuf) {
@@ -162,14 +762,38 @@ impl MainApplication {
             session.save();
         }
 
-        let session = Session::load(file_location.clone())
+        let mut session = Session::load(file_location.clone())
             .unwrap_or_else(|| Session::new(file_location.clone()));
 
-        self.paragraph_viewer.load_paragraphs(
-            file_location,
-            &session.gathering_delimiters(),
-            session.gathering_amount(),
+        self.paragraph_viewer.set_chapter_detector(
+            ChapterDetector::new(&session.chapter_pattern()).unwrap_or_default(),
         );
+        self.paragraph_viewer
+            .set_source_options(session.source_options());
+        let loaded = self
+            .paragraph_viewer
+            .load_paragraphs(file_location.clone(), &session.gathering());
+        if let Err(error) = loaded {
+            dialog::alert_default(&format!(
+                "Could not open {}.\n\n{error:#}",
+                file_location.display()
+            ));
+
+            // Carry on reading the open text the way its own session says to
+            if let Some(open_session) = &self.session {
+                self.paragraph_viewer.set_chapter_detector(
+                    ChapterDetector::new(&open_session.chapter_pattern()).unwrap_or_default(),
+                );
+                self.paragraph_viewer
+                    .set_source_options(open_session.source_options());
+            }
+            return;
+        }
+        let flagged_paragraphs = session.realign_paragraphs(self.paragraph_viewer.paragraphs());
+        session.set_chapters(self.paragraph_viewer.chapters());
+        if !flagged_paragraphs.is_empty() {
+            dialog::alert_default(&flagged_paragraphs_message(&flagged_paragraphs));
+        }
         self.paragraph_viewer
             .show_paragraph_at(session.paragraph_num());
 
@@ -179,6 +803,20 @@ impl MainApplication {
     pub fn run(&mut self) {
         while self.app.wait() {
             if let Some(event) = self.ui_action_receiver.recv() {
+                if event.changes_paragraph() && self.media_io.is_busy() {
+                    match event {
+                        // It was clicked on in the overview, which shouldn't look gone to
+                        UIActions::ShowParagraph(_) => {
+                            self.overview.select(self.paragraph_viewer.paragraph_num())
+                        }
+                        UIActions::FindNext | UIActions::FindPrevious => self
+                            .find_dialog
+                            .set_result("Stop playing or recording to search other paragraphs."),
+                        _ => {}
+                    }
+                    continue;
+                }
+
                 match event {
                     UIActions::Next => {
                         self.paragraph_viewer.show_next_paragraph();
@@ -199,17 +837,142 @@ impl MainApplication {
                     }
                     UIActions::Stop => {
                         self.media_io.stop();
+                        self.update_overview_entry();
                     }
                     UIActions::Record => {
-                        let input_device = self
+                        if self.media_io.is_recording_paused() {
+                            self.media_io.resume_recording();
+                            continue;
+                        }
+
+                        let paragraph_num = self.paragraph_viewer.paragraph_num();
+                        let session = self
                             .session
-                            .as_ref()
-                            .expect("Session should exist on Recording")
-                            .audio_input();
+                            .as_mut()
+                            .expect("Session should exist on Recording");
+
+                        let Some(take_location) = session.new_take_location(paragraph_num) else {
+                            dialog::alert_default("There's no paragraph to record.");
+                            continue;
+                        };
+                        // Punching in always goes to a new take, so the take
+                        // being punched into is left untouched.
+                        let punched_in_take = session
+                            .active_take(paragraph_num)
+                            .filter(|_| self.punch_in_toggle.is_checked());
+                        if let Some(source_file) = &punched_in_take {
+                            if let Err(error) =
+                                check_punch_in_format(session.audio_input(), source_file)
+                            {
+                                dialog::alert_default(&format!("Could not punch in.\n\n{error:#}"));
+                                continue;
+                            }
+                        }
+
+                        self.pending_take = Some((paragraph_num, take_location.clone()));
+                        match punched_in_take {
+                            Some(source_file) => {
+                                let punch_in = PunchIn {
+                                    source_file,
+                                    preroll_secs: session.preroll_secs(),
+                                    output_device: session.audio_output().clone(),
+                                };
+
+                                self.media_io.punch_in(
+                                    session.audio_input(),
+                                    take_location,
+                                    punch_in,
+                                );
+                            }
+                            None => self.media_io.record(session.audio_input(), take_location),
+                        }
+                    }
+                    UIActions::RecordingStarted => {
+                        let Some((paragraph_num, take_location)) = self.pending_take.take() else {
+                            continue;
+                        };
+                        let Some(session) = self.session.as_mut() else {
+                            continue;
+                        };
+
+                        session.add_take(paragraph_num, &take_location);
+                        let num_takes = session.takes(paragraph_num).map_or(0, |takes| takes.len());
+                        self.take_selector.add_choice(&format!("Take {num_takes}"));
+                        self.take_selector.set_value(num_takes as i32 - 1);
+                        self.show_status();
+                    }
+                    UIActions::RecordingFailed => {
+                        self.pending_take = None;
+                        self.load_audio_file();
 
-                        self.media_io.record(input_device);
+                        if let Some(error) = self.media_io.take_recording_error() {
+                            dialog::alert_default(&format!("Could not record.\n\n{error}"));
+                        }
+                    }
+                    UIActions::SelectTake => {
+                        let paragraph_num = self.paragraph_viewer.paragraph_num();
+                        if let Some(session) = self.session.as_mut() {
+                            session.set_active_take(
+                                paragraph_num,
+                                self.take_selector.value() as usize,
+                            );
+                        }
+
+                        self.load_audio_file();
+                    }
+                    UIActions::AudioSkip(pos_ms) => self.media_io.pause_at(pos_ms),
+                    UIActions::MarkRetake => {
+                        self.mark_status(ParagraphStatus::Retake, self.retake_toggle.is_checked())
+                    }
+                    UIActions::MarkApproved => self
+                        .mark_status(ParagraphStatus::Approved, self.approved_toggle.is_checked()),
+                    UIActions::NextWithStatus => self.show_paragraph_with_status(true),
+                    UIActions::PreviousWithStatus => self.show_paragraph_with_status(false),
+                    UIActions::ShowParagraph(paragraph_num) => {
+                        self.paragraph_viewer.show_paragraph_at(paragraph_num);
+                        self.load_audio_file();
+                    }
+                    UIActions::EditNote => {
+                        let paragraph_num = self.paragraph_viewer.paragraph_num();
+                        if let Some(session) = self.session.as_mut() {
+                            session.set_note(paragraph_num, &self.notes_input.value());
+                        }
+                    }
+                    UIActions::AddBookmark => self.add_bookmark(),
+                    UIActions::GoToBookmark(bookmark_index) => self.go_to_bookmark(bookmark_index),
+                    UIActions::RemoveBookmark(bookmark_index) => {
+                        if let Some(session) = self.session.as_mut() {
+                            if let Some(name) = session.bookmark_names().get(bookmark_index) {
+                                session.remove_bookmark(name);
+                            }
+                        }
+                        self.update_bookmarks_menu();
+                    }
+                    UIActions::GoToChapter(chapter_num) => {
+                        let chapter = self
+                            .session
+                            .as_ref()
+                            .and_then(|session| session.chapters().get(chapter_num).cloned());
+                        if let Some(chapter) = chapter {
+                            self.paragraph_viewer
+                                .show_paragraph_at(chapter.first_paragraph);
+                            self.load_audio_file();
+                        }
+                    }
+                    UIActions::ToggleOverview => {
+                        self.overview.set_shown(!self.overview.is_shown());
+                        self.refresh_overview();
+                    }
+                    UIActions::DurationsMeasured => {
+                        self.is_measuring_durations = false;
+                        // Measuring whatever's left every time could go on for as
+                        // long as a take is being recorded
+                        if std::mem::take(&mut self.remeasure_durations) {
+                            self.refresh_overview();
+                        } else {
+                            self.fill_overview();
+                        }
                     }
-                    UIActions::AudioSkip(pos_secs) => self.media_io.pause_at(pos_secs),
                     UIActions::OpenGoto => {
                         self.goto_dialog.show(self.paragraph_viewer.paragraph_num());
 
@@ -222,24 +985,83 @@ impl MainApplication {
                             self.load_audio_file();
                         }
                     }
+                    UIActions::OpenFind => self.find_dialog.show(),
+                    UIActions::FindNext => self.find(true),
+                    UIActions::FindPrevious => self.find(false),
                     UIActions::LoadFile => {
                         if let Some(file_path) = self.open() {
                             self.load_text_file(file_path);
-                            self.load_audio_file();
+                            if self.session.is_some() {
+                                self.load_audio_file();
+                                self.refresh_overview();
+                                self.update_bookmarks_menu();
+                                self.update_chapters_menu();
+                            }
                         }
                     }
+                    UIActions::Export => self.export(),
+                    UIActions::ExportFinished => self.finish_export(),
                     UIActions::OpenPreferences => {
                         // TODO: Split session into AudioPreferences, TextPreferences, and Session.
                         // That way, users can use the Preferences dialog without needing an existing
                         // session open.
                         if let Some(session) = self.session.as_mut() {
-                            self.preferences_dialog.show(session);
-
-                            self.paragraph_viewer.reload_text_with(
-                                &session.gathering_delimiters(),
-                                session.gathering_amount(),
+                            let previous_gathering = session.gathering();
+                            self.preferences_dialog
+                                .show(session, self.paragraph_viewer.text());
+                            let previously_unmatched: Vec<String> = session
+                                .unmatched_recordings()
+                                .into_keys()
+                                .map(String::from)
+                                .collect();
+
+                            // Reading the text again the way it was gathered lets it be
+                            // realigned like an edited one, before it's split the new way
+                            let mut flagged_paragraphs = Vec::new();
+                            if session.source_options() != *self.paragraph_viewer.source_options() {
+                                self.paragraph_viewer
+                                    .set_source_options(session.source_options());
+                                match self.paragraph_viewer.reload_source(&previous_gathering) {
+                                    Ok(()) => {
+                                        flagged_paragraphs = session
+                                            .realign_paragraphs(self.paragraph_viewer.paragraphs())
+                                    }
+                                    Err(error) => dialog::alert_default(&format!(
+                                        "Could not read the text again.\n\n{error:#}"
+                                    )),
+                                }
+                            }
+
+                            self.paragraph_viewer.set_chapter_detector(
+                                ChapterDetector::new(&session.chapter_pattern())
+                                    .unwrap_or_default(),
                             );
+                            self.paragraph_viewer.reload_text_with(&session.gathering());
+                            session.set_paragraphs(self.paragraph_viewer.paragraphs());
+                            session.set_chapters(self.paragraph_viewer.chapters());
+                            if !flagged_paragraphs.is_empty() {
+                                dialog::alert_default(&flagged_paragraphs_message(
+                                    &flagged_paragraphs,
+                                ));
+                            }
+
+                            let newly_unmatched: Vec<&str> = session
+                                .unmatched_recordings()
+                                .into_iter()
+                                .filter(|(paragraph_id, _)| {
+                                    !previously_unmatched.iter().any(|id| id == paragraph_id)
+                                })
+                                .flat_map(|(_, takes)| takes.file_names())
+                                .map(String::as_str)
+                                .collect();
+                            if !newly_unmatched.is_empty() {
+                                dialog::alert_default(&unmatched_recordings_message(
+                                    &newly_unmatched,
+                                ));
+                            }
                             self.load_audio_file();
+                            self.refresh_overview();
+                            self.update_chapters_menu();
                         }
                     }
                     UIActions::About => self.about_dialog.show(),
@@ -257,6 +1079,60 @@ impl MainApplication {
     }
 }
 
+/// How many recordings or paragraphs are named in a warning about them.
+const MAX_LISTED_ITEMS: usize = 10;
+
+fn unmatched_recordings_message(file_names: &[&str]) -> String {
+    let mut message = String::from(
+        "These recordings no longer match any paragraph, since the text has changed or is \
+        split differently:\n",
+    );
+    for file_name in file_names.iter().take(MAX_LISTED_ITEMS) {
+        message.push_str(&format!("\n{file_name}"));
+    }
+    if file_names.len() > MAX_LISTED_ITEMS {
+        message.push_str(&format!(
+            "\n...and {} more",
+            file_names.len() - MAX_LISTED_ITEMS
+        ));
+    }
+    message
+        .push_str("\n\nThey are kept, and come back if the text is split the way it was before.");
+
+    message
+}
+
+/// Escapes the characters menus would otherwise take as part of a path
+/// or a shortcut.
+fn menu_label(name: &str) -> String {
+    name.replace('\\', "\\\\")
+        .replace('/', "\\/")
+        .replace('&', "&&")
+        .replace('_', "\\_")
+}
+
+fn flagged_paragraphs_message(flagged_paragraphs: &[usize]) -> String {
+    let mut paragraph_list = flagged_paragraphs
+        .iter()
+        .take(MAX_LISTED_ITEMS)
+        .map(|paragraph_num| (paragraph_num + 1).to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+    if flagged_paragraphs.len() > MAX_LISTED_ITEMS {
+        paragraph_list.push_str(&format!(
+            " and {} more",
+            flagged_paragraphs.len() - MAX_LISTED_ITEMS
+        ));
+    }
+
+    format!(
+        "The text has changed since it was recorded. Recordings were kept with the \
+        paragraphs they're a reading of, but these paragraphs are new or changed, and need \
+        recording:\n\n{paragraph_list}\n\nChanged paragraphs that were recorded are marked \
+        for a retake."
+    )
+}
+
 fn create_menu_bar(
     action_broadcaster: &fltk::app::Sender<UIActions>,
     flex_column_layout: &mut Flex,
@@ -281,6 +1157,14 @@ fn create_menu_bar(
     //     UIActions::LoadFile,
     // );
 
+    menu_bar.add_emit(
+        "&File/Export\t",
+        Shortcut::Command | 'e',
+        menu::MenuFlag::MenuDivider,
+        *action_broadcaster,
+        UIActions::Export,
+    );
+
     menu_bar.add_emit(
         "&File/Quit\t",
         Shortcut::Command | 'q',
@@ -289,7 +1173,24 @@ fn create_menu_bar(
         UIActions::Quit,
     );
 
+    // View Menu Options
+    menu_bar.add_emit(
+        "&View/Overview\t",
+        Shortcut::Command | 'l',
+        menu::MenuFlag::Toggle,
+        *action_broadcaster,
+        UIActions::ToggleOverview,
+    );
+
     // Edit Menu Options
+    menu_bar.add_emit(
+        "&Edit/Find\t",
+        Shortcut::Command | 'f',
+        menu::MenuFlag::Normal,
+        *action_broadcaster,
+        UIActions::OpenFind,
+    );
+
     menu_bar.add_emit(
         "&Edit/Go To\t",
         Shortcut::Command | 'g',
@@ -306,6 +1207,15 @@ fn create_menu_bar(
         UIActions::OpenPreferences,
     );
 
+    // Bookmarks Menu Options
+    menu_bar.add_emit(
+        "&Bookmarks/Add Bookmark\t",
+        Shortcut::Command | 'b',
+        menu::MenuFlag::MenuDivider,
+        *action_broadcaster,
+        UIActions::AddBookmark,
+    );
+
     // Help Menu Options
     menu_bar.add_emit(
         "&Help/About\t",
@@ -335,13 +1245,39 @@ fn create_widget_layout(
 
     flex_column_layout.fixed(&counter_text, counter_text.label_size());
 
-    // Paragraph Viewer Widget
+    // Speaker of the shown paragraph, only there for scripts
+    let mut speaker_label = Frame::default().with_align(Align::Left | Align::Inside);
+    speaker_label.set_label_font(Font::HelveticaBold);
+    flex_column_layout.fixed(&speaker_label, 20);
+    speaker_label.hide();
+
+    // Paragraph Viewer Widget, with the overview beside it
+    let mut viewer_row = Flex::default_fill().with_type(group::FlexType::Row);
+
     let viewer_text = text::TextBuffer::default();
 
     let mut paragraph_viewer = TextDisplay::default();
     paragraph_viewer.set_buffer(viewer_text);
     paragraph_viewer.wrap_mode(WrapMode::AtColumn, 0);
 
+    let overview = Overview::new(action_broadcaster, &viewer_row);
+    viewer_row.fixed(overview.widget(), 290);
+
+    viewer_row.end();
+
+    // Notes on the paragraph being read
+    let mut notes_input = MultilineInput::default();
+    notes_input.set_tooltip("Notes on this paragraph, like direction for reading it.");
+    notes_input.set_wrap(true);
+    notes_input.set_trigger(CallbackTrigger::Changed);
+    notes_input.emit(*action_broadcaster, UIActions::EditNote);
+    notes_input.deactivate();
+    flex_column_layout.fixed(&notes_input, 50);
+
+    // Audio Waveform
+    let waveform = Waveform::new(action_broadcaster);
+    flex_column_layout.fixed(waveform.widget(), 80);
+
     // Text Navigation and Audio Progress
     let mut progress_bar = HorNiceSlider::default();
     progress_bar.set_bounds(0.0, 0.0);
@@ -369,7 +1305,7 @@ fn create_widget_layout(
     flex_column_layout.fixed(&navigation_pack, 30);
 
     // Playback Widgets
-    let playback_pack = Flex::default_fill().with_type(group::FlexType::Row);
+    let mut playback_pack = Flex::default_fill().with_type(group::FlexType::Row);
 
     let mut stop_button = Button::default().with_label("Stop");
     stop_button.emit(*action_broadcaster, UIActions::Stop);
@@ -383,9 +1319,59 @@ fn create_widget_layout(
     play_pause_button.emit(*action_broadcaster, UIActions::Play);
     play_pause_button.deactivate();
 
+    let mut take_selector = Choice::default();
+    take_selector.emit(*action_broadcaster, UIActions::SelectTake);
+    take_selector.deactivate();
+    playback_pack.fixed(&take_selector, 100);
+
+    let mut punch_in_toggle = CheckButton::default().with_label("Punch In");
+    punch_in_toggle.set_tooltip("Record over the active take from the progress bar's position.");
+    playback_pack.fixed(&punch_in_toggle, 90);
+
     playback_pack.end();
     flex_column_layout.fixed(&playback_pack, 30);
 
+    // Paragraph Status Widgets
+    let mut status_pack = Flex::default_fill().with_type(group::FlexType::Row);
+
+    let status_label = Frame::default().with_align(Align::Left | Align::Inside);
+
+    let mut retake_toggle = CheckButton::default().with_label("Retake");
+    retake_toggle.set_tooltip("Mark the paragraph as needing to be read again.");
+    retake_toggle.emit(*action_broadcaster, UIActions::MarkRetake);
+    retake_toggle.deactivate();
+    status_pack.fixed(&retake_toggle, 80);
+
+    let mut approved_toggle = CheckButton::default().with_label("Approved");
+    approved_toggle.set_tooltip("Mark the paragraph's active take as a keeper.");
+    approved_toggle.emit(*action_broadcaster, UIActions::MarkApproved);
+    approved_toggle.deactivate();
+    status_pack.fixed(&approved_toggle, 90);
+
+    let mut prev_status_button = Button::default().with_label("Previous");
+    prev_status_button.set_tooltip("Go to the previous paragraph with the chosen status.");
+    prev_status_button.emit(*action_broadcaster, UIActions::PreviousWithStatus);
+    status_pack.fixed(&prev_status_button, 80);
+
+    let mut status_filter = Choice::default();
+    for status in ParagraphStatus::ALL {
+        status_filter.add_choice(status.name());
+    }
+    status_filter.set_value(0);
+    status_pack.fixed(&status_filter, 100);
+
+    let mut next_status_button = Button::default().with_label("Next");
+    next_status_button.set_tooltip("Go to the next paragraph with the chosen status.");
+    next_status_button.emit(*action_broadcaster, UIActions::NextWithStatus);
+    status_pack.fixed(&next_status_button, 80);
+
+    status_pack.end();
+    flex_column_layout.fixed(&status_pack, 30);
+
+    // Input Level Meter
+    let level_meter = LevelMeter::new();
+    flex_column_layout.fixed(level_meter.widget(), 20);
+
     // Status Bar
     let status_bar_buf = TextBuffer::default();
 
@@ -410,6 +1396,16 @@ fn create_widget_layout(
         play_button: play_pause_button,
         stop_button,
         record_button,
+        take_selector,
+        punch_in_toggle,
+        level_meter,
+
+        status_label,
+        retake_toggle,
+        approved_toggle,
+        status_filter,
+        overview,
+        notes_input,
 
         next_button: next_button.clone(),
         prev_button: prev_button.clone(),
@@ -417,12 +1413,15 @@ fn create_widget_layout(
 
     let viewer_widgets = ViewerWidgets {
         paragraph_view: paragraph_viewer,
+        speaker_label,
+        viewer_layout: flex_column_layout.clone(),
         next_button,
         prev_button,
         progress_counter: counter_text,
     };
 
     let media_tracking_widgets = MediaTrackingWidgets {
+        waveform,
         progress_bar,
         time_progress_label: audio_progress_text,
         status_bar,
@@ -0,0 +1,63 @@
+use fltk::{
+    app,
+    button::Button,
+    enums::{Align, CallbackTrigger},
+    frame::Frame,
+    input::Input,
+    prelude::*,
+    window::Window,
+};
+
+use crate::ui::{app::UIActions, common::shift_right_by_label};
+
+/// Finds text in the paragraphs, staying open beside the main window so
+/// one match after another can be gone through.
+pub struct FindDialog {
+    window: Window,
+    search_input: Input,
+    result_label: Frame,
+}
+
+impl FindDialog {
+    pub fn new(action_broadcaster: &app::Sender<UIActions>) -> FindDialog {
+        let find_window = Window::default().with_label("Find").with_size(400, 75);
+
+        let mut search_input = Input::new(50, 10, 340, 23, "Find:");
+        shift_right_by_label(&mut search_input);
+        search_input.set_trigger(CallbackTrigger::EnterKeyAlways);
+        search_input.emit(*action_broadcaster, UIActions::FindNext);
+
+        let result_label = Frame::new(10, 43, 180, 23, "").with_align(Align::Left | Align::Inside);
+
+        let mut previous_button = Button::new(200, 43, 90, 23, "Previous");
+        previous_button.emit(*action_broadcaster, UIActions::FindPrevious);
+
+        let mut next_button = Button::new(300, 43, 90, 23, "Next");
+        next_button.emit(*action_broadcaster, UIActions::FindNext);
+
+        find_window.end();
+
+        FindDialog {
+            window: find_window,
+            search_input,
+            result_label,
+        }
+    }
+
+    pub fn show(&mut self) {
+        self.result_label.set_label("");
+        self.window.show();
+        let _ = self.search_input.take_focus();
+    }
+
+    /// Returns the text being searched for.
+    pub fn query(&self) -> String {
+        self.search_input.value()
+    }
+
+    /// Says where the last search ended up, like which paragraph a match
+    /// was found in.
+    pub fn set_result(&mut self, result: &str) {
+        self.result_label.set_label(result);
+    }
+}
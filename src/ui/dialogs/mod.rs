@@ -1,3 +1,4 @@
 pub mod about;
+pub mod find;
 pub mod goto;
 pub mod preferences;
@@ -1,22 +1,31 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::rc::Rc;
 
 use fltk::{
     app,
     button::{Button, CheckButton},
     dialog,
-    enums::{Align, Font, FrameType},
+    enums::{Align, CallbackTrigger, Color, Font, FrameType},
     frame::Frame,
     group::{Flex, FlexType, Group, Tabs},
     input::Input,
     misc::{InputChoice, Spinner},
     prelude::{DisplayExt, GroupExt, InputExt, WidgetBase, WidgetExt, WindowExt},
-    text::{TextBuffer, TextDisplay},
+    text::{TextBuffer, TextDisplay, WrapMode},
     window::Window,
 };
 
 use crate::{
     media::io::{input_device_names, output_device_names, AudioInput},
     sessions::session::Session,
+    text::{
+        chapters::ChapterDetector,
+        encoding::{encoding_for_label, TEXT_ENCODINGS},
+        gathering::Gathering,
+        sentences::{default_abbreviations, SENTENCE_LANGUAGES},
+    },
 };
 
 /// Clears, then adds all choices into the given input.
@@ -47,6 +56,8 @@ pub struct PreferencesDialog {
     window: Window,
 
     project_directory_text: TextDisplay,
+    preroll_secs: Spinner,
+    export_gap_ms: Spinner,
 
     audio_output_name: InputChoice,
 
@@ -58,16 +69,38 @@ pub struct PreferencesDialog {
     custom_gathering: CheckButton,
     gathering_amount: Spinner,
     gathering_delimiters: Input,
+    max_paragraph_length: Spinner,
+    target_duration_secs: Spinner,
+    words_per_minute: Spinner,
+    sentence_language: InputChoice,
+    abbreviations: Input,
+    abbreviation_lists: Rc<RefCell<AbbreviationLists>>,
+    chapter_pattern: Input,
+    read_code_blocks: CheckButton,
+    text_encoding: InputChoice,
+    boundary_pattern: Input,
+    skipped_lines_pattern: Input,
+    splitting_preview: SplittingPreview,
 
     save_button: Button,
 }
 
+/// Abbreviations being edited for each language, as shown in their input,
+/// along with the language they're being shown for.
+#[derive(Default)]
+struct AbbreviationLists {
+    shown_language: String,
+    lists: BTreeMap<String, String>,
+}
+
 struct GeneralTabWidgets {
     project_directory_text: TextDisplay,
+    preroll_secs: Spinner,
+    export_gap_ms: Spinner,
 }
 
 fn create_general_tab() -> GeneralTabWidgets {
-    let general_tab = Group::new(20, 30, 360, 250, "General\t\t");
+    let general_tab = Group::new(20, 30, 360, 390, "General\t\t");
 
     let mut project_widgets_group = Flex::new(20, 40, 360, 50, "Project");
     let project_label_offset = project_widgets_group.label_size();
@@ -111,10 +144,51 @@ fn create_general_tab() -> GeneralTabWidgets {
 
     project_widgets_group.end();
 
+    let mut recording_widgets_group = Flex::new(20, 120, 360, 50, "Recording");
+    let recording_label_offset = recording_widgets_group.label_size();
+    recording_widgets_group.set_align(Align::TopLeft);
+    recording_widgets_group.set_pos(
+        recording_widgets_group.x(),
+        recording_widgets_group.y() + recording_label_offset,
+    );
+    recording_widgets_group.set_label_font(Font::HelveticaBold);
+    recording_widgets_group.set_frame(FrameType::ThinDownFrame);
+    recording_widgets_group.set_type(FlexType::Row);
+    recording_widgets_group.set_margins(80, 10, 200, 10);
+
+    let mut preroll_secs = Spinner::default().with_label("Pre-roll (s):");
+    preroll_secs.set_align(Align::Left);
+    preroll_secs.set_range(0.0, 10.0);
+    preroll_secs.set_tooltip("Seconds played back before punching in.");
+
+    recording_widgets_group.end();
+
+    let mut export_widgets_group = Flex::new(20, 200, 360, 50, "Export");
+    let export_label_offset = export_widgets_group.label_size();
+    export_widgets_group.set_align(Align::TopLeft);
+    export_widgets_group.set_pos(
+        export_widgets_group.x(),
+        export_widgets_group.y() + export_label_offset,
+    );
+    export_widgets_group.set_label_font(Font::HelveticaBold);
+    export_widgets_group.set_frame(FrameType::ThinDownFrame);
+    export_widgets_group.set_type(FlexType::Row);
+    export_widgets_group.set_margins(80, 10, 180, 10);
+
+    let mut export_gap_ms = Spinner::default().with_label("Gap (ms):");
+    export_gap_ms.set_align(Align::Left);
+    export_gap_ms.set_range(0.0, 10000.0);
+    export_gap_ms.set_step(100.0);
+    export_gap_ms.set_tooltip("Silence between paragraphs in the exported audiobook.");
+
+    export_widgets_group.end();
+
     general_tab.end();
 
     GeneralTabWidgets {
         project_directory_text,
+        preroll_secs,
+        export_gap_ms,
     }
 }
 
@@ -125,17 +199,28 @@ struct TextTabWidgets {
     gathering_amount: Spinner,
 
     gathering_delimiters: Input,
+
+    max_paragraph_length: Spinner,
+
+    target_duration_secs: Spinner,
+    words_per_minute: Spinner,
+
+    sentence_language: InputChoice,
+    abbreviations: Input,
 }
 
+/// Shown for when the encoding is worked out from the file.
+const AUTOMATIC_ENCODING: &str = "Automatic";
+
 const TEXT_TAB_LABEL_LENGTH: i32 = 100;
 const TEXT_TAB_INPUT_LENGTH: i32 = 155;
 const TEXT_TAB_CHECKBUTTON_LENGTH: i32 = 70;
 const TEXT_TAB_SPACING: i32 = 10;
 
 fn create_text_tab() -> TextTabWidgets {
-    let text_tab = Group::new(20, 30, 360, 250, "Text\t\t");
+    let text_tab = Group::new(20, 30, 360, 390, "Text\t\t");
 
-    let mut extraction_group = Flex::new(20, 40, 360, 130, "Extraction");
+    let mut extraction_group = Flex::new(20, 40, 360, 210, "Extraction");
     let extraction_group_label_offset = extraction_group.label_size();
     extraction_group.set_align(Align::TopLeft);
     extraction_group.set_pos(
@@ -190,7 +275,103 @@ fn create_text_tab() -> TextTabWidgets {
     ending_with_delimiters_input.deactivate();
     ending_with_group.end();
 
+    let mut max_length_group = Flex::default().with_type(FlexType::Row);
+    extraction_group.fixed(&max_length_group, 30);
+    max_length_group.set_spacing(TEXT_TAB_SPACING);
+
+    let max_length_label = Frame::default();
+    max_length_group.fixed(&max_length_label, TEXT_TAB_LABEL_LENGTH);
+
+    let mut max_length_spinner = Spinner::default()
+        .with_label("Max Length:")
+        .with_align(Align::Left);
+    max_length_spinner.set_range(0.0, 100000.0);
+    max_length_spinner.set_step(100.0);
+    max_length_spinner.set_tooltip(
+        "Paragraphs with more characters than this are split between sentences. \
+        0 leaves them as long as they are.",
+    );
+    max_length_spinner.deactivate();
+    max_length_group.end();
+
+    let mut duration_group = Flex::default().with_type(FlexType::Row);
+    extraction_group.fixed(&duration_group, 30);
+    duration_group.set_spacing(TEXT_TAB_SPACING);
+
+    let target_duration_label = Frame::default();
+    duration_group.fixed(&target_duration_label, TEXT_TAB_LABEL_LENGTH);
+
+    let mut target_duration_spinner = Spinner::default()
+        .with_label("Duration (s):")
+        .with_align(Align::Left);
+    target_duration_spinner.set_range(5.0, 600.0);
+    target_duration_spinner.set_step(5.0);
+    target_duration_spinner.set_tooltip("About how long each paragraph should take to read.");
+    target_duration_spinner.deactivate();
+    duration_group.fixed(&target_duration_spinner, 70);
+
+    let words_per_minute_label = Frame::default();
+    duration_group.fixed(&words_per_minute_label, 80);
+
+    let mut words_per_minute_spinner = Spinner::default()
+        .with_label("Words/min:")
+        .with_align(Align::Left);
+    words_per_minute_spinner.set_range(50.0, 400.0);
+    words_per_minute_spinner.set_step(5.0);
+    words_per_minute_spinner
+        .set_tooltip("How many words you read aloud in a minute, to estimate durations by.");
+    words_per_minute_spinner.deactivate();
+    duration_group.fixed(&words_per_minute_spinner, 70);
+    duration_group.end();
+
     extraction_group.end();
+
+    let mut sentences_group = Flex::new(20, 280, 360, 90, "Sentences");
+    let sentences_group_label_offset = sentences_group.label_size();
+    sentences_group.set_align(Align::TopLeft);
+    sentences_group.set_pos(
+        sentences_group.x(),
+        sentences_group.y() + sentences_group_label_offset,
+    );
+    sentences_group.set_type(FlexType::Column);
+    sentences_group.set_label_font(Font::HelveticaBold);
+    sentences_group.set_frame(FrameType::ThinDownFrame);
+    sentences_group.set_margins(0, TEXT_TAB_SPACING, TEXT_TAB_SPACING, TEXT_TAB_SPACING);
+    sentences_group.set_spacing(TEXT_TAB_SPACING);
+
+    let mut language_group = Flex::default().with_type(FlexType::Row);
+    sentences_group.fixed(&language_group, 30);
+    language_group.set_spacing(TEXT_TAB_SPACING);
+
+    let language_label = Frame::default();
+    language_group.fixed(&language_label, TEXT_TAB_LABEL_LENGTH);
+
+    let mut language_selector = InputChoice::default()
+        .with_label("Language:")
+        .with_align(Align::Left);
+    language_selector.set_tooltip("Which language's abbreviations Smart Sentences knows about.");
+    language_group.fixed(&language_selector, TEXT_TAB_INPUT_LENGTH);
+
+    Frame::default();
+    language_group.end();
+
+    let mut abbreviations_group = Flex::default().with_type(FlexType::Row);
+    sentences_group.fixed(&abbreviations_group, 30);
+    abbreviations_group.set_spacing(TEXT_TAB_SPACING);
+
+    let abbreviations_label = Frame::default();
+    abbreviations_group.fixed(&abbreviations_label, TEXT_TAB_LABEL_LENGTH);
+
+    let mut abbreviations_input = Input::default()
+        .with_label("Abbreviations:")
+        .with_align(Align::Left);
+    abbreviations_input.set_tooltip(
+        "Words Smart Sentences doesn't end a sentence after, separated by spaces, \
+        like \"Mr. Dr. e.g.\"",
+    );
+    abbreviations_group.end();
+
+    sentences_group.end();
     text_tab.end();
 
     TextTabWidgets {
@@ -198,6 +379,232 @@ fn create_text_tab() -> TextTabWidgets {
         gathering_amount: amount_spinner,
         custom_gathering: gatherer_custom_enabler,
         gathering_delimiters: ending_with_delimiters_input,
+        max_paragraph_length: max_length_spinner,
+        target_duration_secs: target_duration_spinner,
+        words_per_minute: words_per_minute_spinner,
+        sentence_language: language_selector,
+        abbreviations: abbreviations_input,
+    }
+}
+
+struct SourcesTabWidgets {
+    chapter_pattern: Input,
+
+    read_code_blocks: CheckButton,
+    text_encoding: InputChoice,
+}
+
+fn create_sources_tab() -> SourcesTabWidgets {
+    let sources_tab = Group::new(20, 30, 360, 390, "Sources\t\t");
+
+    let mut chapters_group = Flex::new(20, 40, 360, 50, "Chapters");
+    let chapters_group_label_offset = chapters_group.label_size();
+    chapters_group.set_align(Align::TopLeft);
+    chapters_group.set_pos(
+        chapters_group.x(),
+        chapters_group.y() + chapters_group_label_offset,
+    );
+    chapters_group.set_type(FlexType::Row);
+    chapters_group.set_label_font(Font::HelveticaBold);
+    chapters_group.set_frame(FrameType::ThinDownFrame);
+    chapters_group.set_margins(
+        TEXT_TAB_LABEL_LENGTH + TEXT_TAB_SPACING,
+        TEXT_TAB_SPACING,
+        TEXT_TAB_SPACING,
+        TEXT_TAB_SPACING,
+    );
+
+    let mut chapter_pattern_input = Input::default()
+        .with_label("Heading Regex:")
+        .with_align(Align::Left);
+    chapter_pattern_input.set_tooltip(
+        "Lines matching this regular expression start a new chapter, \
+        along with headings like \"CHAPTER IV\" or \"# Title\".",
+    );
+
+    chapters_group.end();
+
+    let mut files_group = Flex::new(20, 120, 360, 50, "Files");
+    let files_group_label_offset = files_group.label_size();
+    files_group.set_align(Align::TopLeft);
+    files_group.set_pos(files_group.x(), files_group.y() + files_group_label_offset);
+    files_group.set_type(FlexType::Row);
+    files_group.set_label_font(Font::HelveticaBold);
+    files_group.set_frame(FrameType::ThinDownFrame);
+    files_group.set_margins(
+        TEXT_TAB_LABEL_LENGTH + TEXT_TAB_SPACING,
+        TEXT_TAB_SPACING,
+        TEXT_TAB_SPACING,
+        TEXT_TAB_SPACING,
+    );
+
+    let mut encoding_choice = InputChoice::default()
+        .with_label("Encoding:")
+        .with_align(Align::Left);
+    encoding_choice
+        .set_tooltip("The encoding plain text files are in. Automatic works it out from the file.");
+    files_group.fixed(&encoding_choice, TEXT_TAB_INPUT_LENGTH - 25);
+
+    let mut read_code_blocks_toggle = CheckButton::default().with_label("Read code blocks");
+    read_code_blocks_toggle.set_tooltip(
+        "Keep blocks of code in Markdown, HTML and EPUB files, rather than leaving them out.",
+    );
+
+    files_group.end();
+    sources_tab.end();
+
+    SourcesTabWidgets {
+        chapter_pattern: chapter_pattern_input,
+        read_code_blocks: read_code_blocks_toggle,
+        text_encoding: encoding_choice,
+    }
+}
+
+/// How many of the paragraphs made by the splitting rules are previewed.
+const PREVIEW_PARAGRAPHS: usize = 3;
+
+/// How much of the start of the text is gathered for the preview.
+const PREVIEW_TEXT_LENGTH: usize = 5000;
+
+/// Widgets showing whether the splitting rules are valid, and what the
+/// paragraphs they make look like.
+#[derive(Clone)]
+struct SplittingPreview {
+    boundary_pattern: Input,
+    skipped_lines_pattern: Input,
+    gathering_amount: Spinner,
+
+    status: Frame,
+    preview: TextDisplay,
+    preview_text: Rc<RefCell<String>>,
+}
+
+impl SplittingPreview {
+    /// Checks the splitting rules as they are, showing either what's wrong
+    /// with them or the first few paragraphs they make of the text.
+    fn update(&mut self) {
+        let gathering = Gathering::pattern(
+            &self.boundary_pattern.value(),
+            &self.skipped_lines_pattern.value(),
+            self.gathering_amount.value().max(1.0) as usize,
+        );
+        let mut preview_buffer = self
+            .preview
+            .buffer()
+            .expect("Splitting Preferences: Where's the TextBuffer?");
+
+        match gathering {
+            Ok(gathering) => {
+                let preview_text = self.preview_text.borrow();
+                let paragraphs: Vec<String> = gathering
+                    .paragraphs(&preview_text)
+                    .iter()
+                    .filter(|paragraph| !paragraph.trim().is_empty())
+                    .take(PREVIEW_PARAGRAPHS)
+                    .map(|paragraph| paragraph.trim().to_string())
+                    .collect();
+
+                self.status.set_label_color(Color::Foreground);
+                self.status.set_tooltip("");
+                self.status.set_label(if preview_text.is_empty() {
+                    "Valid. Open a text to preview its paragraphs."
+                } else {
+                    "Valid."
+                });
+                preview_buffer.set_text(&paragraphs.join("\n\n\u{2014}\n\n"));
+            }
+            Err(error) => {
+                // Regex errors point out where they are over several lines,
+                // ending with what's wrong
+                let error = error.to_string();
+                self.status.set_label_color(Color::Red);
+                self.status
+                    .set_label(error.lines().last().unwrap_or_default());
+                self.status.set_tooltip(&error);
+                preview_buffer.set_text("");
+            }
+        }
+    }
+}
+
+struct SplittingTabWidgets {
+    boundary_pattern: Input,
+    skipped_lines_pattern: Input,
+
+    status: Frame,
+    preview: TextDisplay,
+}
+
+fn create_splitting_tab() -> SplittingTabWidgets {
+    let splitting_tab = Group::new(20, 30, 360, 390, "Splitting\t\t");
+
+    let mut rules_group = Flex::new(20, 40, 360, 120, "Regex");
+    let rules_group_label_offset = rules_group.label_size();
+    rules_group.set_align(Align::TopLeft);
+    rules_group.set_pos(rules_group.x(), rules_group.y() + rules_group_label_offset);
+    rules_group.set_type(FlexType::Column);
+    rules_group.set_label_font(Font::HelveticaBold);
+    rules_group.set_frame(FrameType::ThinDownFrame);
+    rules_group.set_margins(
+        TEXT_TAB_LABEL_LENGTH + TEXT_TAB_SPACING,
+        TEXT_TAB_SPACING,
+        TEXT_TAB_SPACING,
+        TEXT_TAB_SPACING,
+    );
+    rules_group.set_spacing(TEXT_TAB_SPACING);
+
+    let mut boundary_pattern_input = Input::default()
+        .with_label("Boundary:")
+        .with_align(Align::Left);
+    boundary_pattern_input.set_trigger(CallbackTrigger::Changed);
+    boundary_pattern_input.set_tooltip(
+        "A piece of text ends after every match of this regular expression, \
+        with Amount pieces making up a paragraph.",
+    );
+    rules_group.fixed(&boundary_pattern_input, 30);
+
+    let mut skipped_lines_pattern_input = Input::default()
+        .with_label("Skip Lines:")
+        .with_align(Align::Left);
+    skipped_lines_pattern_input.set_trigger(CallbackTrigger::Changed);
+    skipped_lines_pattern_input.set_tooltip(
+        "Lines matching this regular expression are left out, like page \
+        numbers or \"* * *\". Leave it empty to keep every line.",
+    );
+    rules_group.fixed(&skipped_lines_pattern_input, 30);
+
+    let status_label = Frame::default().with_align(Align::Left | Align::Inside);
+
+    rules_group.end();
+
+    let mut preview_group = Flex::new(20, 190, 360, 220, "Preview");
+    let preview_group_label_offset = preview_group.label_size();
+    preview_group.set_align(Align::TopLeft);
+    preview_group.set_pos(
+        preview_group.x(),
+        preview_group.y() + preview_group_label_offset,
+    );
+    preview_group.set_label_font(Font::HelveticaBold);
+    preview_group.set_frame(FrameType::ThinDownFrame);
+    preview_group.set_margins(
+        TEXT_TAB_SPACING,
+        TEXT_TAB_SPACING,
+        TEXT_TAB_SPACING,
+        TEXT_TAB_SPACING,
+    );
+
+    let mut preview_display = TextDisplay::default();
+    preview_display.set_buffer(TextBuffer::default());
+    preview_display.wrap_mode(WrapMode::AtBounds, 0);
+
+    preview_group.end();
+    splitting_tab.end();
+
+    SplittingTabWidgets {
+        boundary_pattern: boundary_pattern_input,
+        skipped_lines_pattern: skipped_lines_pattern_input,
+        status: status_label,
+        preview: preview_display,
     }
 }
 
@@ -210,7 +617,7 @@ struct AudioTabWidgets {
 }
 
 fn create_audio_tab() -> AudioTabWidgets {
-    let audio_tab = Group::new(20, 30, 360, 250, "Audio\t\t");
+    let audio_tab = Group::new(20, 30, 360, 390, "Audio\t\t");
 
     let mut output_widget_group = Flex::new(20, 40, 360, 50, "Output");
     output_widget_group.set_type(FlexType::Column);
@@ -276,25 +683,27 @@ fn create_audio_tab() -> AudioTabWidgets {
 impl PreferencesDialog {
     pub fn new() -> PreferencesDialog {
         let preferences_window = Window::default()
-            .with_size(400, 340)
+            .with_size(400, 480)
             .with_label("Preferences");
 
-        let preference_topics = Tabs::new(TEXT_TAB_SPACING, TEXT_TAB_SPACING, 380, 280, "");
+        let preference_topics = Tabs::new(TEXT_TAB_SPACING, TEXT_TAB_SPACING, 380, 420, "");
 
         let general_tab = create_general_tab();
         let mut audio_tab = create_audio_tab();
         let mut text_tab = create_text_tab();
+        let sources_tab = create_sources_tab();
+        let mut splitting_tab = create_splitting_tab();
 
         preference_topics.end();
 
         let mut preferences_window_clone = preferences_window.clone();
-        let mut cancel_button = Button::new(260, 300, 60, 30, "Cancel");
+        let mut cancel_button = Button::new(260, 440, 60, 30, "Cancel");
         cancel_button.set_callback(move |_| {
             preferences_window_clone.hide();
         });
 
         let mut preferences_window_clone = preferences_window.clone();
-        let mut save_button = Button::new(330, 300, 60, 30, "Save");
+        let mut save_button = Button::new(330, 440, 60, 30, "Save");
         save_button.set_callback(move |button| {
             button.deactivate();
             preferences_window_clone.hide();
@@ -331,6 +740,9 @@ impl PreferencesDialog {
         let mut gathering_choice = text_tab.gathering_choice.clone();
         let mut gathering_amount = text_tab.gathering_amount.clone();
         let mut gathering_delimiters = text_tab.gathering_delimiters.clone();
+        let mut max_paragraph_length = text_tab.max_paragraph_length.clone();
+        let mut target_duration_secs = text_tab.target_duration_secs.clone();
+        let mut words_per_minute = text_tab.words_per_minute.clone();
 
         text_tab.custom_gathering.set_callback(move |check_button| {
             if check_button.is_checked() {
@@ -338,6 +750,9 @@ impl PreferencesDialog {
                 gathering_choice.deactivate();
                 gathering_amount.activate();
                 gathering_delimiters.activate();
+                max_paragraph_length.deactivate();
+                target_duration_secs.deactivate();
+                words_per_minute.deactivate();
             } else {
                 gathering_choice.set_value_index(0);
                 gathering_amount.set_value(1.0);
@@ -351,25 +766,93 @@ impl PreferencesDialog {
 
         let mut gathering_amount = text_tab.gathering_amount.clone();
         let mut gathering_delimiters = text_tab.gathering_delimiters.clone();
+        let mut max_paragraph_length = text_tab.max_paragraph_length.clone();
+        let mut target_duration_secs = text_tab.target_duration_secs.clone();
+        let mut words_per_minute = text_tab.words_per_minute.clone();
 
         text_tab.gathering_choice.set_callback(move |input_choice| {
             if let Some(current_choice) = input_choice.value() {
+                // Each choice only goes by some of the settings
+                gathering_amount.deactivate();
+                max_paragraph_length.deactivate();
+                target_duration_secs.deactivate();
+                words_per_minute.deactivate();
+
                 if current_choice == "Paragraphs" {
                     gathering_amount.set_value(1.0);
                     gathering_delimiters.set_value("\t");
-                } else if current_choice == "Sentences" {
+                } else if current_choice == "Source Paragraphs" {
+                    max_paragraph_length.activate();
+                } else if current_choice == "Sentences" || current_choice == "Smart Sentences" {
                     gathering_amount.set_value(4.0);
                     gathering_delimiters.set_value(".?!");
+                } else if current_choice == "Duration" {
+                    target_duration_secs.activate();
+                    words_per_minute.activate();
+                } else if current_choice == "Regex" {
+                    gathering_amount.activate();
                 }
             }
         });
 
+        let abbreviation_lists = Rc::new(RefCell::new(AbbreviationLists::default()));
+        let shown_abbreviation_lists = Rc::clone(&abbreviation_lists);
+        let mut abbreviations_input = text_tab.abbreviations.clone();
+
+        // Each language keeps its own list, so edits to the one shown are
+        // put aside before showing the newly chosen language's.
+        text_tab
+            .sentence_language
+            .set_callback(move |input_choice| {
+                let Some(language) = input_choice.value() else {
+                    return;
+                };
+
+                let mut abbreviation_lists = shown_abbreviation_lists.borrow_mut();
+                let shown_language =
+                    std::mem::replace(&mut abbreviation_lists.shown_language, language.clone());
+                abbreviation_lists
+                    .lists
+                    .insert(shown_language, abbreviations_input.value());
+
+                let abbreviations = abbreviation_lists
+                    .lists
+                    .get(&language)
+                    .cloned()
+                    .unwrap_or_else(|| default_abbreviations(&language).join(" "));
+                abbreviations_input.set_value(&abbreviations);
+            });
+
+        let splitting_preview = SplittingPreview {
+            boundary_pattern: splitting_tab.boundary_pattern.clone(),
+            skipped_lines_pattern: splitting_tab.skipped_lines_pattern.clone(),
+            gathering_amount: text_tab.gathering_amount.clone(),
+            status: splitting_tab.status,
+            preview: splitting_tab.preview,
+            preview_text: Rc::new(RefCell::new(String::new())),
+        };
+
+        let mut preview = splitting_preview.clone();
+        splitting_tab
+            .boundary_pattern
+            .set_callback(move |_| preview.update());
+        let mut preview = splitting_preview.clone();
+        splitting_tab
+            .skipped_lines_pattern
+            .set_callback(move |_| preview.update());
+        let mut preview = splitting_preview.clone();
+        text_tab
+            .gathering_amount
+            .set_callback(move |_| preview.update());
+
         preferences_window.end();
 
         PreferencesDialog {
             window: preferences_window,
 
             project_directory_text: general_tab.project_directory_text,
+            preroll_secs: general_tab.preroll_secs,
+            export_gap_ms: general_tab.export_gap_ms,
 
             audio_output_name: audio_tab.audio_output_name,
             audio_input_name: audio_tab.audio_input_name,
@@ -380,6 +863,19 @@ impl PreferencesDialog {
             custom_gathering: text_tab.custom_gathering,
             gathering_amount: text_tab.gathering_amount,
             gathering_delimiters: text_tab.gathering_delimiters,
+            max_paragraph_length: text_tab.max_paragraph_length,
+            target_duration_secs: text_tab.target_duration_secs,
+            words_per_minute: text_tab.words_per_minute,
+            sentence_language: text_tab.sentence_language,
+            abbreviations: text_tab.abbreviations,
+            abbreviation_lists,
+            chapter_pattern: sources_tab.chapter_pattern,
+            read_code_blocks: sources_tab.read_code_blocks,
+            text_encoding: sources_tab.text_encoding,
+
+            boundary_pattern: splitting_tab.boundary_pattern,
+            skipped_lines_pattern: splitting_tab.skipped_lines_pattern,
+            splitting_preview,
 
             save_button,
         }
@@ -424,9 +920,93 @@ impl PreferencesDialog {
     /// Clears and fills in Text Preferences to the relevant text input
     /// widgets.
     fn populate_text_tab_inputs(&mut self, session: &Session) {
-        let gathering_choice_names = ["Paragraphs", "Sentences"];
+        self.chapter_pattern
+            .set_value(session.chapter_pattern().as_str());
+        self.read_code_blocks
+            .set_checked(session.source_options().read_code_blocks);
+
+        let mut encoding_choice_names = vec![AUTOMATIC_ENCODING];
+        encoding_choice_names.extend(TEXT_ENCODINGS);
+        repopulate_input_choices(&mut self.text_encoding, &encoding_choice_names);
+        set_active_in_input_choices(
+            &mut self.text_encoding,
+            &encoding_choice_names,
+            &session
+                .source_options()
+                .encoding
+                .as_deref()
+                .unwrap_or(AUTOMATIC_ENCODING),
+        );
+
+        let mut sentence_language_names = SENTENCE_LANGUAGES.to_vec();
+        let sentence_language = session.sentence_language();
+        if !sentence_language_names.contains(&sentence_language.as_str()) {
+            sentence_language_names.push(&sentence_language);
+        }
+        repopulate_input_choices(&mut self.sentence_language, &sentence_language_names);
+        set_active_in_input_choices(
+            &mut self.sentence_language,
+            &sentence_language_names,
+            &sentence_language.as_str(),
+        );
+
+        let mut abbreviation_lists = self.abbreviation_lists.borrow_mut();
+        abbreviation_lists.lists = sentence_language_names
+            .iter()
+            .map(|language| {
+                (
+                    language.to_string(),
+                    session.abbreviations(language).join(" "),
+                )
+            })
+            .collect();
+        self.abbreviations
+            .set_value(&abbreviation_lists.lists[&sentence_language]);
+        abbreviation_lists.shown_language = sentence_language;
+        drop(abbreviation_lists);
+
+        let gathering_choice_names = [
+            "Paragraphs",
+            "Source Paragraphs",
+            "Sentences",
+            "Smart Sentences",
+            "Duration",
+            "Regex",
+        ];
         repopulate_input_choices(&mut self.gathering_choice, &gathering_choice_names);
 
+        self.max_paragraph_length
+            .set_value(session.max_paragraph_length() as f64);
+        if session.gathering_choice() == "Source Paragraphs" {
+            self.max_paragraph_length.activate();
+        } else {
+            self.max_paragraph_length.deactivate();
+        }
+
+        self.target_duration_secs
+            .set_value(session.target_duration_secs() as f64);
+        self.words_per_minute
+            .set_value(session.words_per_minute() as f64);
+        if session.gathering_choice() == "Duration" {
+            self.target_duration_secs.activate();
+            self.words_per_minute.activate();
+        } else {
+            self.target_duration_secs.deactivate();
+            self.words_per_minute.deactivate();
+        }
+
+        self.boundary_pattern
+            .set_value(session.boundary_pattern().as_str());
+        self.skipped_lines_pattern
+            .set_value(session.skipped_lines_pattern().as_str());
+        if session.gathering_choice() == "Regex" {
+            self.gathering_amount
+                .set_value(session.gathering_amount() as f64);
+            self.gathering_amount.activate();
+        } else {
+            self.gathering_amount.deactivate();
+        }
+
         if session.gathering_choice() == "Custom" {
             self.custom_gathering.set_checked(true);
             self.gathering_choice
@@ -452,14 +1032,76 @@ impl PreferencesDialog {
     /// Pulls the currently selected values for all Text Preference
     /// widgets and updates the current session accordingly.
     fn save_text_preferences(&self, session: &mut Session) {
-        session.set_gathering_choice(
-            &self
-                .gathering_choice
-                .value()
-                .expect("save_text_preferences: Gathering Choice does not have a value."),
-        );
-        session.set_gathering_amount(self.gathering_amount.value() as usize);
+        let boundary_pattern = self.boundary_pattern.value();
+        let skipped_lines_pattern = self.skipped_lines_pattern.value();
+        let is_splitting_unused = boundary_pattern.is_empty() && skipped_lines_pattern.is_empty();
+        let splitting_error =
+            Gathering::pattern(&boundary_pattern, &skipped_lines_pattern, 1).err();
+
+        let gathering_choice = self
+            .gathering_choice
+            .value()
+            .expect("save_text_preferences: Gathering Choice does not have a value.");
+        let is_regex_chosen = gathering_choice == "Regex";
+        // Otherwise the text would quietly be gathered some other way
+        match &splitting_error {
+            Some(error) if is_regex_chosen => dialog::alert_default(&format!(
+                "Gathering by Regex needs a valid Boundary regex in the Splitting tab, \
+                so neither the gathering nor the splitting regexes were saved.\n\n{error}"
+            )),
+            _ => {
+                session.set_gathering_choice(&gathering_choice);
+                session.set_gathering_amount(self.gathering_amount.value() as usize);
+            }
+        }
         session.set_gathering_delimiters(&self.gathering_delimiters.value());
+        session.set_max_paragraph_length(self.max_paragraph_length.value() as usize);
+        session.set_target_duration_secs(self.target_duration_secs.value() as usize);
+        session.set_words_per_minute(self.words_per_minute.value() as usize);
+
+        let mut abbreviation_lists = self.abbreviation_lists.borrow_mut();
+        let shown_language = abbreviation_lists.shown_language.clone();
+        abbreviation_lists
+            .lists
+            .insert(shown_language.clone(), self.abbreviations.value());
+        for (language, abbreviations) in &abbreviation_lists.lists {
+            session.set_abbreviations(
+                language,
+                abbreviations.split_whitespace().map(String::from).collect(),
+            );
+        }
+        session.set_sentence_language(&shown_language);
+
+        session.set_read_code_blocks(self.read_code_blocks.is_checked());
+        let text_encoding = self
+            .text_encoding
+            .value()
+            .filter(|encoding| !encoding.trim().is_empty() && encoding != AUTOMATIC_ENCODING);
+        match text_encoding.as_deref().map(encoding_for_label).transpose() {
+            Ok(_) => session.set_text_encoding(text_encoding.as_deref()),
+            Err(error) => dialog::alert_default(&format!(
+                "The text encoding is unknown, so it was not saved.\n\n{error}"
+            )),
+        }
+
+        match splitting_error {
+            Some(_) if is_regex_chosen => {}
+            Some(error) if !is_splitting_unused => dialog::alert_default(&format!(
+                "The splitting regexes are invalid, so they were not saved.\n\n{error}"
+            )),
+            _ => {
+                session.set_boundary_pattern(&boundary_pattern);
+                session.set_skipped_lines_pattern(&skipped_lines_pattern);
+            }
+        }
+
+        let chapter_pattern = self.chapter_pattern.value();
+        match ChapterDetector::new(&chapter_pattern) {
+            Ok(_) => session.set_chapter_pattern(&chapter_pattern),
+            Err(error) => dialog::alert_default(&format!(
+                "The chapter heading regex is invalid, so it was not saved.\n\n{error}"
+            )),
+        }
     }
 
     /// Pulls the currently selected values for all audio input widgets
@@ -487,15 +1129,27 @@ impl PreferencesDialog {
         );
     }
 
-    pub fn show(&mut self, session: &mut Session) {
+    /// Shows the preferences for the session, previewing splitting rules
+    /// on the start of the given text.
+    pub fn show(&mut self, session: &mut Session, preview_text: &str) {
         self.save_button.activate();
 
+        let preview_text_end = preview_text
+            .char_indices()
+            .nth(PREVIEW_TEXT_LENGTH)
+            .map_or(preview_text.len(), |(end, _)| end);
+        *self.splitting_preview.preview_text.borrow_mut() =
+            preview_text[..preview_text_end].to_string();
+
         self.project_directory_text
             .buffer()
             .unwrap()
             .set_text(session.project_directory().to_str().unwrap());
+        self.preroll_secs.set_value(session.preroll_secs() as f64);
+        self.export_gap_ms.set_value(session.export_gap_ms() as f64);
         self.populate_audio_tab_inputs(session);
         self.populate_text_tab_inputs(session);
+        self.splitting_preview.update();
 
         self.window.show();
 
@@ -510,6 +1164,8 @@ impl PreferencesDialog {
         let chosen_audio_output_dir = self.project_directory_text.buffer().unwrap().text();
         let audio_output_dir = PathBuf::from(chosen_audio_output_dir);
         session.set_project_directory(audio_output_dir);
+        session.set_preroll_secs(self.preroll_secs.value() as usize);
+        session.set_export_gap_ms(self.export_gap_ms.value() as usize);
 
         self.save_audio_preferences(session);
         self.save_text_preferences(session);
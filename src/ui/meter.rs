@@ -0,0 +1,116 @@
+use std::sync::{Arc, Mutex};
+
+use fltk::{
+    app,
+    button::Button,
+    draw,
+    enums::{Color, FrameType},
+    group::{self, Flex},
+    prelude::*,
+    widget::Widget,
+};
+
+use crate::media::levels::{to_dbfs, InputLevels};
+
+/// The quietest level shown on the meter, in decibels relative to full scale.
+const METER_FLOOR_DB: f32 = -60.0;
+
+/// Shows the input levels while recording, with the RMS as a filled bar and
+/// the peak as a line. The clip indicator stays lit once the input hits full
+/// scale, until it's clicked, so a clipped take can't go unnoticed.
+#[derive(Clone)]
+pub struct LevelMeter {
+    meter_pack: Flex,
+    meter: Widget,
+    clip_indicator: Button,
+    levels: Arc<Mutex<InputLevels>>,
+}
+
+impl LevelMeter {
+    pub fn new() -> LevelMeter {
+        let mut meter_pack = Flex::default_fill().with_type(group::FlexType::Row);
+
+        let levels = Arc::new(Mutex::new(InputLevels::default()));
+
+        let mut meter = Widget::default();
+        meter.set_frame(FrameType::DownBox);
+        meter.set_tooltip("Input level while recording.");
+
+        let meter_levels = levels.clone();
+        meter.draw(move |meter| {
+            let levels = *meter_levels.lock().unwrap();
+            draw_levels(meter, levels);
+        });
+
+        let mut clip_indicator = Button::default().with_label("Clip");
+        clip_indicator.set_tooltip("Lights up when the input clips. Click to clear.");
+        clip_indicator.clear_visible_focus();
+        clip_indicator.set_callback(|clip_indicator| {
+            clip_indicator.set_color(Color::Background);
+            clip_indicator.set_label_color(Color::Foreground);
+            clip_indicator.redraw();
+        });
+        meter_pack.fixed(&clip_indicator, 50);
+
+        meter_pack.end();
+
+        LevelMeter {
+            meter_pack,
+            meter,
+            clip_indicator,
+            levels,
+        }
+    }
+
+    /// The group holding the meter, for fitting it into a layout.
+    pub fn widget(&self) -> &Flex {
+        &self.meter_pack
+    }
+
+    pub fn set_levels(&mut self, levels: InputLevels) {
+        *self.levels.lock().unwrap() = levels;
+        self.meter.redraw();
+
+        if levels.clipped {
+            self.clip_indicator.set_color(Color::Red);
+            self.clip_indicator.set_label_color(Color::White);
+            self.clip_indicator.redraw();
+        }
+
+        app::awake();
+    }
+
+    /// Empties the meter, leaving the clip indicator as it is.
+    pub fn reset(&mut self) {
+        self.set_levels(InputLevels::default());
+    }
+}
+
+/// Where the magnitude falls on the meter, from 0.0 at the floor to 1.0 at full scale.
+fn meter_fraction(magnitude: f32) -> f32 {
+    (1.0 - to_dbfs(magnitude) / METER_FLOOR_DB).clamp(0.0, 1.0)
+}
+
+fn draw_levels(meter: &mut Widget, levels: InputLevels) {
+    let (x, y, w, h) = (meter.x(), meter.y(), meter.w(), meter.h());
+    draw::draw_box(meter.frame(), x, y, w, h, Color::Black);
+
+    // Leave room for the box's border
+    let (x, y, w, h) = (x + 2, y + 2, w - 4, h - 4);
+
+    let rms_color = if to_dbfs(levels.rms) > -6.0 {
+        Color::Red
+    } else if to_dbfs(levels.rms) > -18.0 {
+        Color::Yellow
+    } else {
+        Color::Green
+    };
+    let rms_width = (meter_fraction(levels.rms) * w as f32) as i32;
+    draw::draw_rect_fill(x, y, rms_width, h, rms_color);
+
+    let peak_x = x + (meter_fraction(levels.peak) * w as f32) as i32;
+    if levels.peak > 0.0 {
+        draw::set_draw_color(Color::White);
+        draw::draw_yxline(peak_x.min(x + w - 1), y, y + h - 1);
+    }
+}
@@ -1,3 +1,6 @@
 pub mod app;
 pub mod common;
 pub mod dialogs;
+pub mod meter;
+pub mod overview;
+pub mod waveform;
@@ -0,0 +1,267 @@
+use std::{cell::RefCell, rc::Rc};
+
+use fltk::{
+    app,
+    browser::HoldBrowser,
+    group::{self, Flex},
+    menu::Choice,
+    prelude::*,
+};
+
+use crate::{sessions::status::ParagraphStatus, ui::app::UIActions};
+
+/// How many of a paragraph's words it's listed by.
+const NUM_FIRST_WORDS: usize = 5;
+
+/// Which paragraphs are listed, going by their status.
+const FILTERS: [&str; 4] = ["All", "Unrecorded", "Retake", "Unrecorded or Retake"];
+
+/// A paragraph as it's listed in the overview.
+pub struct OverviewEntry {
+    first_words: String,
+    status: ParagraphStatus,
+    duration_ms: Option<usize>,
+}
+
+impl OverviewEntry {
+    pub fn new(paragraph: &str, status: ParagraphStatus, duration_ms: Option<usize>) -> Self {
+        let mut words = paragraph.split_whitespace();
+        let mut first_words = words
+            .by_ref()
+            .take(NUM_FIRST_WORDS)
+            .collect::<Vec<&str>>()
+            .join(" ");
+        if words.next().is_some() {
+            first_words.push('…');
+        }
+
+        OverviewEntry {
+            first_words,
+            status,
+            duration_ms,
+        }
+    }
+
+    /// Returns the browser line listing the paragraph, a column at a time.
+    fn line(&self, paragraph_num: usize) -> String {
+        let duration = self
+            .duration_ms
+            .map(|duration_ms| {
+                let duration_secs = duration_ms / 1000;
+                format!("{}:{:02}", duration_secs / 60, duration_secs % 60)
+            })
+            .unwrap_or_default();
+
+        // An @ starting the column would otherwise be read as formatting,
+        // which "@." turns off for the rest of it
+        let first_words = if self.first_words.starts_with('@') {
+            format!("@.{}", self.first_words)
+        } else {
+            self.first_words.clone()
+        };
+
+        format!(
+            "{}\t{first_words}\t{}\t{duration}",
+            paragraph_num + 1,
+            self.status.name()
+        )
+    }
+}
+
+#[derive(Default)]
+struct OverviewState {
+    /// Every paragraph's entry, by paragraph number.
+    entries: Vec<OverviewEntry>,
+    /// Numbers of the paragraphs listed, in order.
+    listed: Vec<usize>,
+}
+
+/// Lists every paragraph with its first words, status and how long its
+/// active take is, so any of them can be gone to with a click. Shown
+/// beside the paragraph being read, when toggled on.
+#[derive(Clone)]
+pub struct Overview {
+    layout: Flex,
+    panel: Flex,
+    filter: Choice,
+    browser: HoldBrowser,
+    state: Rc<RefCell<OverviewState>>,
+}
+
+impl Overview {
+    /// Creates the panel within the layout, which it's laid out again
+    /// with whenever it's shown or hidden.
+    pub fn new(action_broadcaster: &app::Sender<UIActions>, layout: &Flex) -> Overview {
+        let state = Rc::new(RefCell::new(OverviewState::default()));
+
+        let mut panel = Flex::default_fill().with_type(group::FlexType::Column);
+
+        let mut filter = Choice::default();
+        for filter_name in FILTERS {
+            filter.add_choice(filter_name);
+        }
+        filter.set_value(0);
+        filter.set_tooltip("Which paragraphs are listed.");
+        panel.fixed(&filter, 30);
+
+        let mut browser = HoldBrowser::default();
+        browser.set_column_widths(&[45, 120, 80, 45]);
+        browser.set_column_char('\t');
+
+        let select_state = state.clone();
+        let broadcaster = *action_broadcaster;
+        browser.set_callback(move |browser| {
+            let selected_line = browser.value();
+            if selected_line < 1 {
+                return;
+            }
+
+            if let Some(&paragraph_num) =
+                select_state.borrow().listed.get(selected_line as usize - 1)
+            {
+                broadcaster.send(UIActions::ShowParagraph(paragraph_num));
+            }
+        });
+
+        panel.end();
+        panel.hide();
+
+        let mut overview = Overview {
+            layout: layout.clone(),
+            panel,
+            filter,
+            browser,
+            state,
+        };
+
+        let mut filtered_overview = overview.clone();
+        overview
+            .filter
+            .set_callback(move |_| filtered_overview.fill());
+
+        overview
+    }
+
+    pub fn widget(&self) -> &Flex {
+        &self.panel
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.panel.visible()
+    }
+
+    pub fn set_shown(&mut self, shown: bool) {
+        if shown {
+            self.panel.show();
+        } else {
+            self.panel.hide();
+        }
+
+        self.layout.layout();
+    }
+
+    /// Lists the entries of every paragraph, in order.
+    pub fn set_entries(&mut self, entries: Vec<OverviewEntry>) {
+        self.state.borrow_mut().entries = entries;
+        self.fill();
+    }
+
+    /// Changes how a single paragraph is listed, like after recording it.
+    pub fn set_entry(&mut self, paragraph_num: usize, entry: OverviewEntry) {
+        let mut state = self.state.borrow_mut();
+        let Some(old_entry) = state.entries.get_mut(paragraph_num) else {
+            return;
+        };
+        *old_entry = entry;
+
+        let listed_line = state
+            .listed
+            .iter()
+            .position(|&listed| listed == paragraph_num);
+        match listed_line {
+            Some(line_num)
+                if is_listed(self.filter.value(), state.entries[paragraph_num].status) =>
+            {
+                self.browser.set_text(
+                    line_num as i32 + 1,
+                    &state.entries[paragraph_num].line(paragraph_num),
+                );
+            }
+            // Whether it's listed at all may have changed with its status
+            _ => {
+                drop(state);
+                self.fill();
+            }
+        }
+    }
+
+    /// Highlights the paragraph, if it's listed.
+    pub fn select(&mut self, paragraph_num: usize) {
+        let listed_line = self
+            .state
+            .borrow()
+            .listed
+            .iter()
+            .position(|&listed| listed == paragraph_num);
+
+        if let Some(line_num) = listed_line {
+            self.browser.select(line_num as i32 + 1);
+        }
+    }
+
+    /// Lists the paragraphs the filter lets through.
+    fn fill(&mut self) {
+        let filter = self.filter.value();
+        let mut state = self.state.borrow_mut();
+
+        self.browser.clear();
+        let listed: Vec<usize> = state
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| is_listed(filter, entry.status))
+            .map(|(paragraph_num, entry)| {
+                self.browser.add(&entry.line(paragraph_num));
+                paragraph_num
+            })
+            .collect();
+        state.listed = listed;
+    }
+}
+
+/// Returns whether the filter, chosen by its index, lets the status through.
+fn is_listed(filter: i32, status: ParagraphStatus) -> bool {
+    match filter {
+        1 => status == ParagraphStatus::Unrecorded,
+        2 => status == ParagraphStatus::Retake,
+        3 => matches!(
+            status,
+            ParagraphStatus::Unrecorded | ParagraphStatus::Retake
+        ),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_list_first_words_status_and_duration() {
+        let entry = OverviewEntry::new(
+            "  It was a dark\nand stormy night.",
+            ParagraphStatus::Recorded,
+            Some(83_500),
+        );
+        assert_eq!(entry.line(4), "5\tIt was a dark and…\tRecorded\t1:23");
+
+        let entry = OverviewEntry::new("Email me@home.", ParagraphStatus::Unrecorded, None);
+        assert_eq!(entry.line(0), "1\tEmail me@home.\tUnrecorded\t");
+
+        let entry = OverviewEntry::new("@home, at last.", ParagraphStatus::Unrecorded, None);
+        assert_eq!(entry.line(0), "1\t@.@home, at last.\tUnrecorded\t");
+
+        assert!(is_listed(3, ParagraphStatus::Retake));
+        assert!(!is_listed(1, ParagraphStatus::Approved));
+    }
+}
@@ -0,0 +1,118 @@
+use std::sync::{Arc, Mutex};
+
+use fltk::{
+    app, draw,
+    enums::{Color, Event, FrameType},
+    prelude::*,
+    widget::Widget,
+};
+
+use crate::{media::peaks::Peaks, ui::app::UIActions};
+
+#[derive(Default)]
+struct WaveformState {
+    peaks: Option<Arc<Peaks>>,
+    position_ms: usize,
+    total_ms: usize,
+}
+
+/// Draws the loaded recording's waveform with a playhead at the current
+/// position. Clicking or dragging across it seeks like the progress bar.
+#[derive(Clone)]
+pub struct Waveform {
+    widget: Widget,
+    state: Arc<Mutex<WaveformState>>,
+}
+
+impl Waveform {
+    pub fn new(action_broadcaster: &app::Sender<UIActions>) -> Waveform {
+        let state = Arc::new(Mutex::new(WaveformState::default()));
+
+        let mut widget = Widget::default();
+        widget.set_frame(FrameType::DownBox);
+
+        let draw_state = state.clone();
+        widget.draw(move |widget| {
+            draw_waveform(widget, &draw_state.lock().unwrap());
+        });
+
+        let handle_state = state.clone();
+        let broadcaster = *action_broadcaster;
+        widget.handle(move |widget, event| match event {
+            Event::Push | Event::Drag => {
+                let total_ms = handle_state.lock().unwrap().total_ms;
+                if total_ms == 0 || !widget.active() {
+                    return false;
+                }
+
+                let click_fraction = ((app::event_x() - widget.x()) as f64
+                    / widget.w().max(1) as f64)
+                    .clamp(0.0, 1.0);
+                broadcaster.send(UIActions::AudioSkip(
+                    (click_fraction * total_ms as f64) as usize,
+                ));
+                true
+            }
+            _ => false,
+        });
+
+        Waveform { widget, state }
+    }
+
+    pub fn widget(&self) -> &Widget {
+        &self.widget
+    }
+
+    /// Shows the peaks of a newly loaded recording, or a flat line without one.
+    pub fn set_peaks(&mut self, peaks: Option<Arc<Peaks>>) {
+        self.state.lock().unwrap().peaks = peaks;
+        self.widget.redraw();
+    }
+
+    pub fn set_position(&mut self, position_ms: usize) {
+        self.state.lock().unwrap().position_ms = position_ms;
+        self.widget.redraw();
+    }
+
+    pub fn set_total(&mut self, total_ms: usize) {
+        self.state.lock().unwrap().total_ms = total_ms;
+        self.widget.redraw();
+    }
+}
+
+fn draw_waveform(widget: &mut Widget, state: &WaveformState) {
+    let (x, y, w, h) = (widget.x(), widget.y(), widget.w(), widget.h());
+    draw::draw_box(widget.frame(), x, y, w, h, Color::Background2);
+
+    // Leave room for the box's border
+    let (x, y, w, h) = (x + 2, y + 2, w - 4, h - 4);
+    if w <= 0 || h <= 0 {
+        return;
+    }
+
+    let center_y = y + h / 2;
+    let half_height = (h / 2) as f32;
+
+    draw::set_draw_color(Color::DarkBlue);
+    match &state.peaks {
+        Some(peaks) if !peaks.is_empty() => {
+            for column in 0..w {
+                let (min, max) = peaks.column(column as usize, w as usize);
+                let top = center_y - (max * half_height) as i32;
+                let bottom = center_y - (min * half_height) as i32;
+
+                draw::draw_yxline(x + column, top, bottom);
+            }
+        }
+        _ => draw::draw_xyline(x, center_y, x + w - 1),
+    }
+
+    if state.total_ms > 0 {
+        let playhead_x = x
+            + (state.position_ms.min(state.total_ms) as f64 / state.total_ms as f64
+                * (w - 1) as f64) as i32;
+
+        draw::set_draw_color(Color::Red);
+        draw::draw_yxline(playhead_x, y, y + h - 1);
+    }
+}